@@ -6,4 +6,6 @@ docker build . --tag redis-single
 cd ..
 cd ./cluster
 docker build . --tag redis-cluster
-
+cd ..
+cd ./sentinel
+docker build . --tag redis-sentinel
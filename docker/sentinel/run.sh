@@ -0,0 +1,20 @@
+#!/bin/bash
+
+wait_for()
+{
+    until $1 | grep -q "$2"
+    do
+        sleep 0.5
+    done
+}
+
+redis-server --port 6390 &> master.log &
+redis-server --port 6391 --replicaof 127.0.0.1 6390 &> replica.log &
+redis-sentinel ./sentinel.conf &> sentinel.log &
+
+wait_for "redis-cli -p 26379 sentinel get-master-addr-by-name mymaster" 6390
+wait_for "redis-cli -p 26379 sentinel replicas mymaster" 6391
+
+echo SENTINEL READY!
+
+sleep infinity
@@ -0,0 +1,306 @@
+use std::{
+    ops::{Deref, DerefMut},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crossbeam_queue::ArrayQueue;
+use parking_lot::{Condvar, Mutex};
+use redis::{Client, Connection, ConnectionLike, RedisResult};
+
+use crate::{
+    errors::RedisPoolError,
+    pool::{DEFAULT_CON_LIMIT, DEFAULT_POOL_SIZE},
+};
+
+/// Synchronous counterpart of [`ConnectionFactory`](crate::factory::ConnectionFactory).
+pub trait BlockingConnectionFactory<C>
+where
+    C: ConnectionLike,
+{
+    fn create(&self) -> RedisResult<C>;
+}
+
+impl BlockingConnectionFactory<Connection> for Client {
+    fn create(&self) -> RedisResult<Connection> {
+        self.get_connection()
+    }
+}
+
+pub type SingleBlockingRedisPool = BlockingRedisPool<Client, Connection>;
+
+impl From<Client> for SingleBlockingRedisPool {
+    fn from(value: Client) -> Self {
+        BlockingRedisPool::new(value, DEFAULT_POOL_SIZE, Some(DEFAULT_CON_LIMIT))
+    }
+}
+
+/// Pool of synchronous [`redis::ConnectionLike`] connections, for code running
+/// outside of an async runtime.
+///
+/// Like [`RedisPool`](crate::RedisPool) it keeps up to `pool_size` idle
+/// connections in a queue and hands out at most `con_limit` connections at a
+/// time, blocking the calling thread while waiting for one.
+pub struct BlockingRedisPool<F, C>
+where
+    F: BlockingConnectionFactory<C> + Send + Sync + Clone,
+    C: ConnectionLike + Send,
+{
+    factory: F,
+    shared: Arc<BlockingShared<C>>,
+    con_limit: Option<usize>,
+}
+
+/// State shared between a blocking pool and the connections it handed out.
+struct BlockingShared<C> {
+    queue: ArrayQueue<C>,
+    permits: Option<Permits>,
+}
+
+impl<F, C> BlockingRedisPool<F, C>
+where
+    F: BlockingConnectionFactory<C> + Send + Sync + Clone,
+    C: ConnectionLike + Send,
+{
+    pub fn new(factory: F, pool_size: usize, con_limit: Option<usize>) -> Self {
+        if pool_size > con_limit.unwrap_or(usize::MAX) {
+            tracing::warn!("pool size is greater then connection limit");
+        }
+
+        BlockingRedisPool {
+            factory,
+            shared: Arc::new(BlockingShared {
+                queue: ArrayQueue::new(pool_size),
+                permits: con_limit.map(Permits::new),
+            }),
+            con_limit,
+        }
+    }
+
+    /// Blocks until a connection is free.
+    pub fn acquire(&self) -> Result<BlockingRedisPoolConnection<C>, RedisPoolError> {
+        self.acquire_until(None)
+    }
+
+    /// Blocks up to `timeout` for a connection permit, returning
+    /// [`RedisPoolError::Timeout`] if none was released in time.
+    pub fn acquire_with_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<BlockingRedisPoolConnection<C>, RedisPoolError> {
+        self.acquire_until(Some(Instant::now() + timeout))
+    }
+
+    /// Returns [`RedisPoolError::PoolExhausted`] right away if the connection
+    /// limit is reached.
+    pub fn try_acquire(&self) -> Result<BlockingRedisPoolConnection<C>, RedisPoolError> {
+        if let Some(permits) = &self.shared.permits {
+            if !permits.try_acquire() {
+                return Err(RedisPoolError::PoolExhausted);
+            }
+        }
+
+        self.checkout()
+    }
+
+    fn acquire_until(
+        &self,
+        deadline: Option<Instant>,
+    ) -> Result<BlockingRedisPoolConnection<C>, RedisPoolError> {
+        if let Some(permits) = &self.shared.permits {
+            if !permits.acquire(deadline) {
+                return Err(RedisPoolError::Timeout);
+            }
+        }
+
+        self.checkout()
+    }
+
+    /// Hands out a connection once a permit was taken, giving the permit back
+    /// if no connection could be created.
+    fn checkout(&self) -> Result<BlockingRedisPoolConnection<C>, RedisPoolError> {
+        match self.acquire_connection() {
+            Ok(con) => Ok(BlockingRedisPoolConnection {
+                con: Some(con),
+                shared: self.shared.clone(),
+            }),
+            Err(e) => {
+                if let Some(permits) = &self.shared.permits {
+                    permits.release();
+                }
+
+                Err(e.into())
+            }
+        }
+    }
+
+    fn acquire_connection(&self) -> RedisResult<C> {
+        while let Some(mut con) = self.shared.queue.pop() {
+            if con.check_connection() {
+                return Ok(con);
+            }
+
+            tracing::warn!("bad redis connection");
+        }
+
+        self.factory.create()
+    }
+
+    pub fn factory(&self) -> &F {
+        &self.factory
+    }
+
+    pub fn pool_size(&self) -> usize {
+        self.shared.queue.capacity()
+    }
+
+    pub fn con_limit(&self) -> Option<usize> {
+        self.con_limit
+    }
+
+    /// Number of idle connections waiting in the queue.
+    pub fn idle(&self) -> usize {
+        self.shared.queue.len()
+    }
+}
+
+impl<F, C> Clone for BlockingRedisPool<F, C>
+where
+    F: BlockingConnectionFactory<C> + Send + Sync + Clone,
+    C: ConnectionLike + Send,
+{
+    fn clone(&self) -> Self {
+        BlockingRedisPool {
+            factory: self.factory.clone(),
+            shared: self.shared.clone(),
+            con_limit: self.con_limit,
+        }
+    }
+}
+
+impl<F, C> Deref for BlockingRedisPool<F, C>
+where
+    F: BlockingConnectionFactory<C> + Send + Sync + Clone,
+    C: ConnectionLike + Send,
+{
+    type Target = F;
+
+    fn deref(&self) -> &Self::Target {
+        &self.factory
+    }
+}
+
+/// Counting semaphore blocking the calling thread, the synchronous
+/// counterpart of the pool's tokio semaphore.
+struct Permits {
+    available: Mutex<usize>,
+    released: Condvar,
+}
+
+impl Permits {
+    fn new(permits: usize) -> Self {
+        Permits {
+            available: Mutex::new(permits),
+            released: Condvar::new(),
+        }
+    }
+
+    /// Takes a permit, waiting until `deadline` if set, and returns whether one
+    /// was taken.
+    fn acquire(&self, deadline: Option<Instant>) -> bool {
+        let mut available = self.available.lock();
+
+        while *available == 0 {
+            match deadline {
+                Some(deadline) => {
+                    if self
+                        .released
+                        .wait_until(&mut available, deadline)
+                        .timed_out()
+                        && *available == 0
+                    {
+                        return false;
+                    }
+                }
+                None => self.released.wait(&mut available),
+            }
+        }
+
+        *available -= 1;
+        true
+    }
+
+    fn try_acquire(&self) -> bool {
+        let mut available = self.available.lock();
+
+        if *available == 0 {
+            return false;
+        }
+
+        *available -= 1;
+        true
+    }
+
+    fn release(&self) {
+        *self.available.lock() += 1;
+        self.released.notify_one();
+    }
+}
+
+/// Connection handed out by a [`BlockingRedisPool`], queued again when dropped.
+///
+/// It implements [`redis::ConnectionLike`] through redis' blanket impl for
+/// [`DerefMut`] types.
+pub struct BlockingRedisPoolConnection<C>
+where
+    C: ConnectionLike + Send,
+{
+    // This field can be safley unwrapped because it is always initialized to Some
+    // and only set to None when dropped or detached
+    con: Option<C>,
+    shared: Arc<BlockingShared<C>>,
+}
+
+impl<C> BlockingRedisPoolConnection<C>
+where
+    C: ConnectionLike + Send,
+{
+    pub fn detach(mut self) -> C {
+        self.con.take().unwrap()
+    }
+}
+
+impl<C> Drop for BlockingRedisPoolConnection<C>
+where
+    C: ConnectionLike + Send,
+{
+    fn drop(&mut self) {
+        if let Some(con) = self.con.take().filter(|con| con.is_open()) {
+            let _ = self.shared.queue.push(con);
+        }
+
+        if let Some(permits) = &self.shared.permits {
+            permits.release();
+        }
+    }
+}
+
+impl<C> Deref for BlockingRedisPoolConnection<C>
+where
+    C: ConnectionLike + Send,
+{
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        self.con.as_ref().unwrap()
+    }
+}
+
+impl<C> DerefMut for BlockingRedisPoolConnection<C>
+where
+    C: ConnectionLike + Send,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.con.as_mut().unwrap()
+    }
+}
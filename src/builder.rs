@@ -0,0 +1,370 @@
+use rand::Rng;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    errors::ConfigError,
+    factory::ConnectionFactory,
+    health::{HealthCheck, PingCheck},
+    hooks::{HookFuture, PoolHooks},
+    permit::DEFAULT_PRIORITY_AGING,
+    pool::{RedisPool, DEFAULT_CON_LIMIT, DEFAULT_POOL_NAME, DEFAULT_POOL_SIZE},
+    reset::ResetPolicy,
+    retry::RetryPolicy,
+};
+
+/// Settings shared by every clone of a [`RedisPool`].
+#[derive(Debug, Clone)]
+pub(crate) struct PoolConfig {
+    pub(crate) name: String,
+    pub(crate) pool_size: usize,
+    pub(crate) con_limit: Option<usize>,
+    pub(crate) acquire_timeout: Option<Duration>,
+    pub(crate) idle_timeout: Option<Duration>,
+    pub(crate) max_lifetime: Option<Duration>,
+    pub(crate) max_lifetime_jitter: Option<Duration>,
+    pub(crate) min_idle: usize,
+    pub(crate) retry: RetryPolicy,
+    /// Failure threshold and open timeout of the circuit breaker.
+    pub(crate) circuit_breaker: Option<(u32, Duration)>,
+    pub(crate) priority_aging: Duration,
+    pub(crate) tenant_limits: HashMap<String, usize>,
+    pub(crate) default_tenant_limit: Option<usize>,
+    pub(crate) reset_policy: ResetPolicy,
+}
+
+/// Without an explicit jitter up to a tenth of the max lifetime is cut off.
+const DEFAULT_LIFETIME_JITTER_DIVISOR: u32 = 10;
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            name: DEFAULT_POOL_NAME.to_owned(),
+            pool_size: DEFAULT_POOL_SIZE,
+            con_limit: Some(DEFAULT_CON_LIMIT),
+            acquire_timeout: None,
+            idle_timeout: None,
+            max_lifetime: None,
+            max_lifetime_jitter: None,
+            min_idle: 0,
+            retry: RetryPolicy::default(),
+            circuit_breaker: None,
+            priority_aging: DEFAULT_PRIORITY_AGING,
+            tenant_limits: HashMap::new(),
+            default_tenant_limit: None,
+            reset_policy: ResetPolicy::default(),
+        }
+    }
+}
+
+impl PoolConfig {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.pool_size == 0 {
+            return Err(ConfigError::ZeroPoolSize);
+        }
+
+        if let Some(con_limit) = self.con_limit {
+            if con_limit == 0 {
+                return Err(ConfigError::ZeroConLimit);
+            }
+
+            if self.pool_size > con_limit {
+                return Err(ConfigError::PoolSizeExceedsConLimit {
+                    pool_size: self.pool_size,
+                    con_limit,
+                });
+            }
+        }
+
+        if self
+            .acquire_timeout
+            .is_some_and(|timeout| timeout.is_zero())
+        {
+            return Err(ConfigError::ZeroAcquireTimeout);
+        }
+
+        if let Some(idle_timeout) = self.idle_timeout {
+            if idle_timeout.is_zero() {
+                return Err(ConfigError::ZeroIdleTimeout);
+            }
+        }
+
+        if self.min_idle > self.pool_size {
+            return Err(ConfigError::MinIdleExceedsPoolSize {
+                min_idle: self.min_idle,
+                pool_size: self.pool_size,
+            });
+        }
+
+        if self.retry.max_attempts() == 0 {
+            return Err(ConfigError::ZeroMaxAttempts);
+        }
+
+        if self
+            .circuit_breaker
+            .is_some_and(|(failure_threshold, _)| failure_threshold == 0)
+        {
+            return Err(ConfigError::ZeroFailureThreshold);
+        }
+
+        if let Some((tenant, _)) = self.tenant_limits.iter().find(|(_, &max)| max == 0) {
+            return Err(ConfigError::ZeroTenantLimit(tenant.clone()));
+        }
+
+        if self.default_tenant_limit == Some(0) {
+            return Err(ConfigError::ZeroDefaultTenantLimit);
+        }
+
+        if self.needs_maintenance() && !crate::rt::can_spawn() {
+            return Err(ConfigError::NoRuntime);
+        }
+
+        if self.max_lifetime_jitter.is_some() && self.max_lifetime.is_none() {
+            return Err(ConfigError::JitterWithoutMaxLifetime);
+        }
+
+        if let Some(max_lifetime) = self.max_lifetime {
+            if max_lifetime.is_zero() {
+                return Err(ConfigError::ZeroMaxLifetime);
+            }
+
+            if self
+                .max_lifetime_jitter
+                .is_some_and(|jitter| jitter >= max_lifetime)
+            {
+                return Err(ConfigError::JitterExceedsMaxLifetime);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether a background task is needed to close idle connections or keep
+    /// the minimum of idle connections.
+    pub(crate) fn needs_maintenance(&self) -> bool {
+        self.idle_timeout.is_some() || self.min_idle > 0
+    }
+
+    /// Picks the end of life for a new connection, shortened by a random jitter
+    /// so connections created together are not all recycled at once.
+    pub(crate) fn lifetime_deadline(&self) -> Option<Instant> {
+        let max_lifetime = self.max_lifetime?;
+        let jitter = self
+            .max_lifetime_jitter
+            .unwrap_or(max_lifetime / DEFAULT_LIFETIME_JITTER_DIVISOR);
+        let jitter = rand::rng().random_range(Duration::ZERO..=jitter);
+
+        Some(Instant::now() + max_lifetime - jitter)
+    }
+}
+
+/// Builder for a [`RedisPool`], created with [`RedisPool::builder`].
+///
+/// Unlike [`RedisPool::new`] every setting is checked when calling
+/// [`RedisPoolBuilder::build`] so a bad configuration is rejected at startup.
+pub struct RedisPoolBuilder<F, C>
+where
+    F: ConnectionFactory<C> + Send + Sync + Clone,
+    C: redis::aio::ConnectionLike + Send,
+{
+    factory: F,
+    config: PoolConfig,
+    health_check: Arc<dyn HealthCheck<C>>,
+    hooks: PoolHooks<C>,
+}
+
+impl<F, C> RedisPoolBuilder<F, C>
+where
+    F: ConnectionFactory<C> + Send + Sync + Clone + 'static,
+    C: redis::aio::ConnectionLike + Send + 'static,
+{
+    pub fn new(factory: F) -> Self {
+        RedisPoolBuilder {
+            factory,
+            config: PoolConfig::default(),
+            health_check: Arc::new(PingCheck),
+            hooks: PoolHooks::default(),
+        }
+    }
+
+    /// Name of the pool, used as the `pool` label of its metrics.
+    ///
+    /// Defaults to [`DEFAULT_POOL_NAME`].
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.config.name = name.into();
+        self
+    }
+
+    /// Maximum number of idle connections kept in the pool queue.
+    ///
+    /// Defaults to [`DEFAULT_POOL_SIZE`].
+    pub fn pool_size(mut self, pool_size: usize) -> Self {
+        self.config.pool_size = pool_size;
+        self
+    }
+
+    /// Maximum number of connections handed out at the same time, `None` for no limit.
+    ///
+    /// Defaults to [`DEFAULT_CON_LIMIT`].
+    pub fn con_limit(mut self, con_limit: Option<usize>) -> Self {
+        self.config.con_limit = con_limit;
+        self
+    }
+
+    /// Maximum time [`RedisPool::acquire`] waits for a permit and a connection.
+    ///
+    /// Defaults to waiting forever.
+    pub fn acquire_timeout(mut self, timeout: Duration) -> Self {
+        self.config.acquire_timeout = Some(timeout);
+        self
+    }
+
+    /// Closes connections which were idle in the pool queue for longer than `timeout`.
+    ///
+    /// This spawns a background task on the current runtime which stops
+    /// once the last clone of the pool is dropped. Defaults to never closing them.
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.config.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Recycles connections once they are older than `lifetime` instead of
+    /// handing them out again. Defaults to keeping connections forever.
+    pub fn max_lifetime(mut self, lifetime: Duration) -> Self {
+        self.config.max_lifetime = Some(lifetime);
+        self
+    }
+
+    /// Upper bound of the random time cut off each connection's max lifetime,
+    /// so connections created together are not all recycled at the same moment.
+    ///
+    /// Defaults to a tenth of the max lifetime.
+    pub fn max_lifetime_jitter(mut self, jitter: Duration) -> Self {
+        self.config.max_lifetime_jitter = Some(jitter);
+        self
+    }
+
+    /// Number of idle connections the pool keeps in its queue, as long as the
+    /// connection limit allows it.
+    ///
+    /// Connections are created by [`RedisPool::warm_up`] and refilled by a
+    /// background task on the current runtime after they are evicted.
+    /// Defaults to zero.
+    pub fn min_idle(mut self, min_idle: usize) -> Self {
+        self.config.min_idle = min_idle;
+        self
+    }
+
+    /// Retries creating a connection with an exponential backoff when the
+    /// factory fails.
+    ///
+    /// Defaults to a single attempt.
+    pub fn retry(mut self, retry: RetryPolicy) -> Self {
+        self.config.retry = retry;
+        self
+    }
+
+    /// Fails acquires needing a new connection with
+    /// [`RedisPoolError::CircuitOpen`](crate::errors::RedisPoolError::CircuitOpen)
+    /// once the factory failed `failure_threshold` times in a row, without
+    /// calling it. After `open_timeout` a single creation is let through, and
+    /// its success closes the circuit again.
+    ///
+    /// Defaults to no circuit breaker.
+    pub fn circuit_breaker(mut self, failure_threshold: u32, open_timeout: Duration) -> Self {
+        self.config.circuit_breaker = Some((failure_threshold, open_timeout));
+        self
+    }
+
+    /// Wait after which a task waiting in
+    /// [`acquire_with_priority`](RedisPool::acquire_with_priority) is served
+    /// as if it had the next higher priority. A zero duration serves every
+    /// waiter in order of arrival.
+    ///
+    /// Defaults to [`DEFAULT_PRIORITY_AGING`].
+    pub fn priority_aging(mut self, aging: Duration) -> Self {
+        self.config.priority_aging = aging;
+        self
+    }
+
+    /// Limits `tenant` to `max` connections from
+    /// [`acquire_for`](RedisPool::acquire_for), on top of the pool's
+    /// connection limit.
+    pub fn tenant_limit(mut self, tenant: impl Into<String>, max: usize) -> Self {
+        self.config.tenant_limits.insert(tenant.into(), max);
+        self
+    }
+
+    /// Limits the tenants without a [`tenant_limit`](Self::tenant_limit) to
+    /// `max` connections each.
+    ///
+    /// Defaults to no limit besides the pool's.
+    pub fn default_tenant_limit(mut self, max: usize) -> Self {
+        self.config.default_tenant_limit = Some(max);
+        self
+    }
+
+    /// What happens to a returned connection whose session state was changed
+    /// by the commands sent through it, such as an unfinished `MULTI`.
+    ///
+    /// Defaults to [`ResetPolicy::Reset`].
+    pub fn reset_policy(mut self, policy: ResetPolicy) -> Self {
+        self.config.reset_policy = policy;
+        self
+    }
+
+    /// Check run on queued connections before they are handed out.
+    ///
+    /// Defaults to [`PingCheck`].
+    pub fn health_check(mut self, health_check: impl HealthCheck<C> + 'static) -> Self {
+        self.health_check = Arc::new(health_check);
+        self
+    }
+
+    /// Runs `hook` on every new connection, for example to send `CLIENT SETNAME`
+    /// or `SELECT`. An error fails the acquire that created the connection.
+    pub fn after_connect<H>(mut self, hook: H) -> Self
+    where
+        H: for<'a> Fn(&'a mut C) -> HookFuture<'a> + Send + Sync + 'static,
+    {
+        self.hooks.after_connect = Some(Arc::new(hook));
+        self
+    }
+
+    /// Runs `hook` on a queued connection before it is handed out again,
+    /// an error drops the connection and the next one is tried.
+    pub fn before_acquire<H>(mut self, hook: H) -> Self
+    where
+        H: for<'a> Fn(&'a mut C) -> HookFuture<'a> + Send + Sync + 'static,
+    {
+        self.hooks.before_acquire = Some(Arc::new(hook));
+        self
+    }
+
+    /// Runs `hook` on a returned connection before it is queued, for example
+    /// to reset its state. An error drops the connection.
+    ///
+    /// The hook runs in a task spawned on the current runtime, the
+    /// connection keeps counting against the connection limit until it is done.
+    pub fn after_release<H>(mut self, hook: H) -> Self
+    where
+        H: for<'a> Fn(&'a mut C) -> HookFuture<'a> + Send + Sync + 'static,
+    {
+        self.hooks.after_release = Some(Arc::new(hook));
+        self
+    }
+
+    /// Validates the configuration and creates the pool.
+    pub fn build(self) -> Result<RedisPool<F, C>, ConfigError> {
+        self.config.validate()?;
+        Ok(RedisPool::with_config(
+            self.factory,
+            self.config,
+            self.health_check,
+            self.hooks,
+        ))
+    }
+}
@@ -1,53 +1,191 @@
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crossbeam_queue::ArrayQueue;
-//use redis::aio::{Monitor, MultiplexedConnection, PubSub};
-use redis::{aio::ConnectionLike, Cmd, RedisFuture, Value};
-use tokio::sync::OwnedSemaphorePermit;
+use redis::aio::{ConnectionLike, MultiplexedConnection, PubSub};
+use redis::{Cmd, RedisFuture, Value};
+
+use crate::{
+    hooks::run_hook,
+    permit::PoolPermit,
+    pool::PoolShared,
+    reset::{ResetPolicy, SessionState},
+    rt,
+    tenant::TenantPermit,
+};
 
 pub struct RedisPoolConnection<C>
 where
-    C: redis::aio::ConnectionLike + Send,
+    C: redis::aio::ConnectionLike + Send + 'static,
 {
     // This field can be safley unwrapped because it is always initialized to Some
     // and only set to None when dropped or detached
     con: Option<C>,
-    permit: Option<OwnedSemaphorePermit>,
-    queue: Arc<ArrayQueue<C>>,
+    expires_at: Option<Instant>,
+    permit: Option<PoolPermit>,
+    tenant: Option<TenantPermit>,
+    shared: Arc<PoolShared<C>>,
+    checked_out_at: Instant,
+    /// Changes made by the commands sent through this guard.
+    state: SessionState,
+}
+
+/// A connection waiting in the pool queue along with the time it was returned
+/// and the end of its lifetime.
+pub(crate) struct IdleConnection<C> {
+    pub(crate) con: C,
+    pub(crate) idle_since: Instant,
+    pub(crate) expires_at: Option<Instant>,
+}
+
+impl<C> IdleConnection<C> {
+    pub(crate) fn new(con: C, expires_at: Option<Instant>) -> Self {
+        IdleConnection {
+            con,
+            idle_since: Instant::now(),
+            expires_at,
+        }
+    }
+
+    /// Whether the connection outlived its lifetime or sat idle longer than `idle_timeout`.
+    pub(crate) fn is_stale(&self, idle_timeout: Option<Duration>) -> bool {
+        is_expired(self.expires_at)
+            || idle_timeout.is_some_and(|idle_timeout| self.idle_since.elapsed() >= idle_timeout)
+    }
+}
+
+pub(crate) fn is_expired(expires_at: Option<Instant>) -> bool {
+    expires_at.is_some_and(|expires_at| Instant::now() >= expires_at)
 }
 
 impl<C> RedisPoolConnection<C>
 where
-    C: redis::aio::ConnectionLike + Send,
+    C: redis::aio::ConnectionLike + Send + 'static,
 {
-    pub fn new(con: C, permit: Option<OwnedSemaphorePermit>, queue: Arc<ArrayQueue<C>>) -> Self {
+    pub(crate) fn new(
+        con: C,
+        expires_at: Option<Instant>,
+        permit: Option<PoolPermit>,
+        shared: Arc<PoolShared<C>>,
+    ) -> Self {
         RedisPoolConnection {
             con: Some(con),
+            expires_at,
             permit,
-            queue,
+            tenant: None,
+            shared,
+            checked_out_at: Instant::now(),
+            state: SessionState::default(),
         }
     }
 
+    pub(crate) fn set_tenant(&mut self, tenant: TenantPermit) {
+        self.tenant = Some(tenant);
+    }
+
+    /// Takes the connection out of the pool, releasing its permit right away
+    /// so it no longer counts against the connection limit.
     pub fn detach(mut self) -> C {
+        self.shared.checked_in(self.checked_out_at.elapsed());
         self.con.take().unwrap()
     }
+
+    /// Like [`detach`](Self::detach), keeping the permit until the returned
+    /// connection is dropped so it still counts against the connection limit.
+    pub fn detach_with_permit(mut self) -> DetachedConnection<C> {
+        self.shared.checked_in(self.checked_out_at.elapsed());
+        self.shared.stats.detached();
+
+        DetachedConnection {
+            con: self.con.take(),
+            permit: self.permit.take(),
+            tenant: self.tenant.take(),
+            shared: self.shared.clone(),
+        }
+    }
 }
 
 impl<C> Drop for RedisPoolConnection<C>
 where
-    C: redis::aio::ConnectionLike + Send,
+    C: redis::aio::ConnectionLike + Send + 'static,
 {
     fn drop(&mut self) {
-        if let Some(con) = self.con.take() {
-            let _ = self.queue.push(con);
+        if let Some(mut con) = self.con.take() {
+            let held = self.checked_out_at.elapsed();
+
+            if is_expired(self.expires_at)
+                || self.shared.is_closed()
+                || !self.shared.is_reusable(&con)
+            {
+                self.shared.checked_in(held);
+                return;
+            }
+
+            let mut reset = None;
+
+            if self.state.is_dirty() && self.shared.reset_policy != ResetPolicy::Keep {
+                if self.shared.reset_policy == ResetPolicy::Reset {
+                    reset = self.state.reset();
+                }
+
+                if reset.is_none() {
+                    tracing::debug!("redis connection closed after changing its state");
+                    self.shared.checked_in(held);
+                    return;
+                }
+            }
+
+            let after_release = self.shared.hooks.after_release.clone();
+
+            if reset.is_none() && after_release.is_none() {
+                self.shared
+                    .push_idle(IdleConnection::new(con, self.expires_at));
+                self.shared.checked_in(held);
+                return;
+            }
+
+            // The reset and hook need a runtime to run, without one the
+            // connection is dropped.
+            if !rt::can_spawn() {
+                self.shared.checked_in(held);
+                return;
+            };
+
+            // The permits are held until the reset and hook are done so the connection
+            // still counts against the connection limit and tenant quota.
+            let permit = self.permit.take();
+            let tenant = self.tenant.take();
+            let shared = self.shared.clone();
+            let expires_at = self.expires_at;
+
+            rt::spawn(async move {
+                let reset = match reset {
+                    Some(reset) => reset.query_async::<()>(&mut con).await,
+                    None => Ok(()),
+                };
+
+                match reset {
+                    Ok(()) => match run_hook(&after_release, &mut con).await {
+                        Ok(()) => {
+                            shared.push_idle(IdleConnection::new(con, expires_at));
+                        }
+                        Err(e) => {
+                            tracing::debug!("redis connection rejected by after_release: {}", e)
+                        }
+                    },
+                    Err(e) => tracing::debug!("redis connection failed to reset: {}", e),
+                }
+
+                shared.checked_in(held);
+                drop((permit, tenant));
+            });
         }
     }
 }
 
 impl<C> Deref for RedisPoolConnection<C>
 where
-    C: redis::aio::ConnectionLike + Send,
+    C: redis::aio::ConnectionLike + Send + 'static,
 {
     type Target = C;
 
@@ -58,7 +196,7 @@ where
 
 impl<C> DerefMut for RedisPoolConnection<C>
 where
-    C: redis::aio::ConnectionLike + Send,
+    C: redis::aio::ConnectionLike + Send + 'static,
 {
     fn deref_mut(&mut self) -> &mut Self::Target {
         self.con.as_mut().unwrap()
@@ -67,9 +205,10 @@ where
 
 impl<C> ConnectionLike for RedisPoolConnection<C>
 where
-    C: redis::aio::ConnectionLike + Send,
+    C: redis::aio::ConnectionLike + Send + 'static,
 {
     fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        self.state.observe(cmd);
         self.con.as_mut().unwrap().req_packed_command(cmd)
     }
 
@@ -79,6 +218,7 @@ where
         offset: usize,
         count: usize,
     ) -> redis::RedisFuture<'a, Vec<redis::Value>> {
+        self.state.observe_pipeline(cmd);
         self.con
             .as_mut()
             .unwrap()
@@ -90,61 +230,125 @@ where
     }
 }
 
-/*
-impl RedisPoolConnection<MultiplexedConnection> {
-    pub fn into_pubsub(mut self) -> RedisPoolPubSub {
-        let permit = self.permit.take();
-        let queue = self.queue.clone();
-        RedisPoolPubSub::new(self.detach().into_pubsub(), permit, queue)
+/// Connection returned by [`RedisPoolConnection::detach_with_permit`].
+///
+/// It is never returned to the pool, but holds a connection permit until it is
+/// dropped and is counted as [`detached`](crate::PoolStatus::detached).
+pub struct DetachedConnection<C>
+where
+    C: redis::aio::ConnectionLike + Send + 'static,
+{
+    // This field can be safley unwrapped because it is always initialized to Some
+    // and only set to None when dropped or unwrapped
+    con: Option<C>,
+    permit: Option<PoolPermit>,
+    tenant: Option<TenantPermit>,
+    shared: Arc<PoolShared<C>>,
+}
+
+impl<C> DetachedConnection<C>
+where
+    C: redis::aio::ConnectionLike + Send + 'static,
+{
+    /// Returns the connection, releasing its permit.
+    pub fn into_inner(mut self) -> C {
+        self.con.take().unwrap()
     }
+}
 
-    pub fn into_monitor(self) -> Monitor {
-        self.detach().into_monitor()
+impl<C> Drop for DetachedConnection<C>
+where
+    C: redis::aio::ConnectionLike + Send + 'static,
+{
+    fn drop(&mut self) {
+        self.con.take();
+        self.permit.take();
+        self.tenant.take();
+        self.shared.stats.detached_dropped();
     }
 }
 
+impl<C> Deref for DetachedConnection<C>
+where
+    C: redis::aio::ConnectionLike + Send + 'static,
+{
+    type Target = C;
+
+    fn deref(&self) -> &Self::Target {
+        self.con.as_ref().unwrap()
+    }
+}
+
+impl<C> DerefMut for DetachedConnection<C>
+where
+    C: redis::aio::ConnectionLike + Send + 'static,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.con.as_mut().unwrap()
+    }
+}
+
+impl<C> ConnectionLike for DetachedConnection<C>
+where
+    C: redis::aio::ConnectionLike + Send + 'static,
+{
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        self.con.as_mut().unwrap().req_packed_command(cmd)
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a redis::Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> redis::RedisFuture<'a, Vec<redis::Value>> {
+        self.con
+            .as_mut()
+            .unwrap()
+            .req_packed_commands(cmd, offset, count)
+    }
+
+    fn get_db(&self) -> i64 {
+        self.con.as_ref().unwrap().get_db()
+    }
+}
+
+/// Dedicated Pub/Sub connection created by [`SingleRedisPool::pubsub`](crate::SingleRedisPool::pubsub).
+///
+/// It holds a connection permit of the pool until it is dropped or detached.
 pub struct RedisPoolPubSub {
     // This field can be safley unwrapped because it is always initialized to Some
     // and only set to None when dropped or detached
     pubsub: Option<PubSub>,
-    permit: Option<OwnedSemaphorePermit>,
-    queue: Arc<ArrayQueue<MultiplexedConnection>>,
+    permit: Option<PoolPermit>,
+    shared: Arc<PoolShared<MultiplexedConnection>>,
+    checked_out_at: Instant,
 }
 
 impl RedisPoolPubSub {
-    pub fn new(
+    pub(crate) fn new(
         pubsub: PubSub,
-        permit: Option<OwnedSemaphorePermit>,
-        queue: Arc<ArrayQueue<MultiplexedConnection>>,
+        permit: Option<PoolPermit>,
+        shared: Arc<PoolShared<MultiplexedConnection>>,
     ) -> Self {
         RedisPoolPubSub {
             pubsub: Some(pubsub),
             permit,
-            queue,
+            shared,
+            checked_out_at: Instant::now(),
         }
     }
 
     pub fn detach(mut self) -> PubSub {
         self.pubsub.take().unwrap()
     }
-
-    pub async fn into_connection(mut self) -> RedisPoolConnection<MultiplexedConnection> {
-        let permit = self.permit.take();
-        let queue = self.queue.clone();
-        RedisPoolConnection::new(self.detach().into_connection().await, permit, queue)
-    }
 }
 
 impl Drop for RedisPoolPubSub {
     fn drop(&mut self) {
-        if let Some(pubsub) = self.pubsub.take() {
-            let permit = self.permit.take();
-            let queue = self.queue.clone();
-            tokio::spawn(async move {
-                let _permit = permit;
-                let _ = queue.push(pubsub.into_connection().await);
-            });
-        }
+        self.pubsub.take();
+        self.permit.take();
+        self.shared.checked_in(self.checked_out_at.elapsed());
     }
 }
 
@@ -160,4 +364,4 @@ impl DerefMut for RedisPoolPubSub {
     fn deref_mut(&mut self) -> &mut Self::Target {
         self.pubsub.as_mut().unwrap()
     }
-}*/
+}
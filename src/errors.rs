@@ -7,4 +7,58 @@ pub enum RedisPoolError {
     Redis(#[from] redis::RedisError),
     #[error(transparent)]
     AcquireError(#[from] AcquireError),
+    #[error(transparent)]
+    Config(#[from] ConfigError),
+    #[error("timed out while acquiring a connection")]
+    Timeout,
+    #[error("no connections are available in the pool")]
+    PoolExhausted,
+    #[error("the pool is closed")]
+    Closed,
+    #[error("the circuit breaker is open after repeated connection failures")]
+    CircuitOpen,
+}
+
+/// Invalid pool settings rejected by [`RedisPoolBuilder::build`](crate::builder::RedisPoolBuilder::build)
+/// or by the factories built from a list of endpoints.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    #[error("pool size must be greater than zero")]
+    ZeroPoolSize,
+    #[error("connection limit must be greater than zero")]
+    ZeroConLimit,
+    #[error("pool size {pool_size} is greater than the connection limit {con_limit}")]
+    PoolSizeExceedsConLimit { pool_size: usize, con_limit: usize },
+    #[error("acquire timeout must be greater than zero")]
+    ZeroAcquireTimeout,
+    #[error("idle timeout must be greater than zero")]
+    ZeroIdleTimeout,
+    #[error("max lifetime must be greater than zero")]
+    ZeroMaxLifetime,
+    #[error("max lifetime jitter must be less than the max lifetime")]
+    JitterExceedsMaxLifetime,
+    #[error("max lifetime jitter requires a max lifetime")]
+    JitterWithoutMaxLifetime,
+    #[error("min idle {min_idle} is greater than the pool size {pool_size}")]
+    MinIdleExceedsPoolSize { min_idle: usize, pool_size: usize },
+    #[error("retry policy must allow at least one attempt")]
+    ZeroMaxAttempts,
+    #[error("circuit breaker failure threshold must be greater than zero")]
+    ZeroFailureThreshold,
+    #[error("connection limit of tenant {0:?} must be greater than zero")]
+    ZeroTenantLimit(String),
+    #[error("default tenant connection limit must be greater than zero")]
+    ZeroDefaultTenantLimit,
+    #[error("a connection limit can not be added to or removed from a built pool")]
+    ToggledConLimit,
+    #[error("background pool tasks must be created within a runtime")]
+    NoRuntime,
+    #[error("at least one endpoint is required")]
+    NoEndpoints,
+    #[error("shard weight must be greater than zero")]
+    ZeroShardWeight,
+    #[error("virtual nodes per shard must be greater than zero")]
+    ZeroVirtualNodes,
+    #[error("shard {0} was added twice")]
+    DuplicateShard(String),
 }
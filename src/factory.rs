@@ -10,6 +10,15 @@ where
     C: ConnectionLike,
 {
     async fn create(&self) -> RedisResult<C>;
+
+    /// Whether a connection created by this factory can still be handed out
+    /// and queued again. The pool closes the connections which can not,
+    /// whatever its health check.
+    ///
+    /// Defaults to `true`.
+    fn is_reusable(&self, _con: &C) -> bool {
+        true
+    }
 }
 
 #[async_trait]
@@ -0,0 +1,134 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use redis::{aio::MultiplexedConnection, Client, RedisResult};
+
+use crate::{
+    errors::ConfigError,
+    factory::ConnectionFactory,
+    pool::{RedisPool, DEFAULT_CON_LIMIT, DEFAULT_POOL_SIZE},
+};
+
+pub const DEFAULT_FAILBACK_INTERVAL: Duration = Duration::from_secs(30);
+
+pub type FailoverRedisPool = RedisPool<FailoverFactory, MultiplexedConnection>;
+
+impl From<FailoverFactory> for FailoverRedisPool {
+    fn from(value: FailoverFactory) -> Self {
+        RedisPool::new(value, DEFAULT_POOL_SIZE, Some(DEFAULT_CON_LIMIT))
+    }
+}
+
+/// Creates connections to the first reachable endpoint of an ordered list of
+/// clients, the first client having the highest priority.
+///
+/// The last endpoint a connection was created to is remembered and tried
+/// first. While it is not the preferred endpoint, a creation at most every
+/// failback interval tries the higher priority endpoints again. Connections
+/// already in the pool stay on their endpoint, set a
+/// [`max_lifetime`](crate::RedisPoolBuilder::max_lifetime) to move them back.
+#[derive(Clone)]
+pub struct FailoverFactory {
+    clients: Arc<[Client]>,
+    current: Arc<AtomicUsize>,
+    last_probe: Arc<parking_lot::Mutex<Instant>>,
+    failback_interval: Duration,
+}
+
+impl FailoverFactory {
+    pub fn new(clients: Vec<Client>) -> Result<Self, ConfigError> {
+        if clients.is_empty() {
+            return Err(ConfigError::NoEndpoints);
+        }
+
+        Ok(FailoverFactory {
+            clients: clients.into(),
+            current: Arc::new(AtomicUsize::new(0)),
+            last_probe: Arc::new(parking_lot::Mutex::new(Instant::now())),
+            failback_interval: DEFAULT_FAILBACK_INTERVAL,
+        })
+    }
+
+    /// Sets how often the higher priority endpoints are tried again after a
+    /// failover, 30 seconds by default.
+    pub fn failback_interval(mut self, interval: Duration) -> Self {
+        self.failback_interval = interval;
+        self
+    }
+
+    pub fn clients(&self) -> &[Client] {
+        &self.clients
+    }
+
+    /// Index of the endpoint new connections are created to.
+    pub fn current(&self) -> usize {
+        self.current.load(Ordering::Relaxed)
+    }
+
+    /// Whether the higher priority endpoints should be tried again.
+    fn failback_due(&self) -> bool {
+        let mut last_probe = self.last_probe.lock();
+
+        if last_probe.elapsed() < self.failback_interval {
+            return false;
+        }
+
+        *last_probe = Instant::now();
+        true
+    }
+
+    fn switch_to(&self, from: usize, to: usize) {
+        if from != to
+            && self
+                .current
+                .compare_exchange(from, to, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            *self.last_probe.lock() = Instant::now();
+            tracing::warn!("redis endpoint {} is now used instead of {}", to, from);
+        }
+    }
+}
+
+#[async_trait]
+impl ConnectionFactory<MultiplexedConnection> for FailoverFactory {
+    async fn create(&self) -> RedisResult<MultiplexedConnection> {
+        let current = self.current();
+
+        if current > 0 && self.failback_due() {
+            for index in 0..current {
+                match self.clients[index].get_multiplexed_async_connection().await {
+                    Ok(con) => {
+                        self.switch_to(current, index);
+                        return Ok(con);
+                    }
+                    Err(e) => tracing::debug!("redis endpoint {} still unavailable: {}", index, e),
+                }
+            }
+        }
+
+        let mut last_err = None;
+
+        for index in (current..self.clients.len()).chain(0..current) {
+            match self.clients[index].get_multiplexed_async_connection().await {
+                Ok(con) => {
+                    self.switch_to(current, index);
+                    return Ok(con);
+                }
+                Err(e) => {
+                    tracing::debug!("redis endpoint {} unavailable: {}", index, e);
+                    last_err = Some(e);
+                }
+            }
+        }
+
+        // The list of clients is never empty, so at least one attempt failed.
+        Err(last_err.unwrap())
+    }
+}
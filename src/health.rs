@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::{aio::ConnectionLike, Cmd, ErrorKind, RedisError, RedisResult, Value};
+
+/// Decides whether a connection taken from the pool queue can be handed out.
+///
+/// Connections failing the check are dropped and the pool tries the next
+/// queued connection, or creates a new one.
+#[async_trait]
+pub trait HealthCheck<C>: Send + Sync
+where
+    C: ConnectionLike + Send,
+{
+    /// Checks `con`, which was idle in the pool queue for `idle`.
+    async fn check(&self, con: &mut C, idle: Duration) -> RedisResult<()>;
+}
+
+/// Sends a pipelined `UNWATCH` and `PING` on every checkout.
+///
+/// This is the default health check of a pool.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PingCheck;
+
+#[async_trait]
+impl<C> HealthCheck<C> for PingCheck
+where
+    C: ConnectionLike + Send,
+{
+    async fn check(&self, con: &mut C, _idle: Duration) -> RedisResult<()> {
+        let (pong,) = redis::Pipeline::with_capacity(2)
+            .cmd("UNWATCH")
+            .ignore()
+            .cmd("PING")
+            .arg(1)
+            .query_async::<(usize,)>(con)
+            .await?;
+
+        if pong != 1 {
+            return Err(RedisError::from((
+                ErrorKind::UnexpectedReturnType,
+                "connection ping returned wrong value",
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Hands out queued connections without checking them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoCheck;
+
+#[async_trait]
+impl<C> HealthCheck<C> for NoCheck
+where
+    C: ConnectionLike + Send,
+{
+    async fn check(&self, _con: &mut C, _idle: Duration) -> RedisResult<()> {
+        Ok(())
+    }
+}
+
+/// Only runs the inner check once a connection was idle for at least `min_idle`.
+#[derive(Debug, Clone)]
+pub struct IdleCheck<H> {
+    min_idle: Duration,
+    inner: H,
+}
+
+impl<H> IdleCheck<H> {
+    pub fn new(min_idle: Duration, inner: H) -> Self {
+        IdleCheck { min_idle, inner }
+    }
+}
+
+#[async_trait]
+impl<C, H> HealthCheck<C> for IdleCheck<H>
+where
+    C: ConnectionLike + Send,
+    H: HealthCheck<C>,
+{
+    async fn check(&self, con: &mut C, idle: Duration) -> RedisResult<()> {
+        if idle < self.min_idle {
+            return Ok(());
+        }
+
+        self.inner.check(con, idle).await
+    }
+}
+
+/// Sends a custom command, failing only if redis returns an error.
+///
+/// Useful for proxies which reject `UNWATCH`, for example with `redis::cmd("PING")`.
+#[derive(Clone)]
+pub struct CommandCheck {
+    cmd: Cmd,
+}
+
+impl CommandCheck {
+    pub fn new(cmd: Cmd) -> Self {
+        CommandCheck { cmd }
+    }
+}
+
+#[async_trait]
+impl<C> HealthCheck<C> for CommandCheck
+where
+    C: ConnectionLike + Send,
+{
+    async fn check(&self, con: &mut C, _idle: Duration) -> RedisResult<()> {
+        self.cmd.query_async::<Value>(con).await?;
+        Ok(())
+    }
+}
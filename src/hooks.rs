@@ -0,0 +1,37 @@
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use redis::RedisResult;
+
+/// Future returned by a connection hook, an error rejects the connection.
+pub type HookFuture<'a> = Pin<Box<dyn Future<Output = RedisResult<()>> + Send + 'a>>;
+
+/// Async callback run against a connection at some point of its life in the pool.
+pub(crate) type Hook<C> = Arc<dyn for<'a> Fn(&'a mut C) -> HookFuture<'a> + Send + Sync>;
+
+/// Lifecycle hooks set with [`RedisPoolBuilder`](crate::RedisPoolBuilder).
+pub(crate) struct PoolHooks<C> {
+    /// Runs once on every new connection, before it is used.
+    pub(crate) after_connect: Option<Hook<C>>,
+    /// Runs on a queued connection before it is handed out again.
+    pub(crate) before_acquire: Option<Hook<C>>,
+    /// Runs when a connection is returned, before it is queued.
+    pub(crate) after_release: Option<Hook<C>>,
+}
+
+impl<C> Default for PoolHooks<C> {
+    fn default() -> Self {
+        PoolHooks {
+            after_connect: None,
+            before_acquire: None,
+            after_release: None,
+        }
+    }
+}
+
+/// Runs `hook` if it is set.
+pub(crate) async fn run_hook<C>(hook: &Option<Hook<C>>, con: &mut C) -> RedisResult<()> {
+    match hook {
+        Some(hook) => hook(con).await,
+        None => Ok(()),
+    }
+}
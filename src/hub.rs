@@ -0,0 +1,356 @@
+use std::{collections::HashMap, pin::pin, time::Duration};
+
+use futures_util::StreamExt;
+use redis::{
+    aio::{PubSubSink, PubSubStream},
+    Client, Msg, RedisResult,
+};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    mpsc, oneshot,
+};
+
+use crate::{
+    errors::RedisPoolError,
+    rt::{self, Task},
+};
+
+/// Messages buffered per channel before slow subscriptions start lagging.
+pub const DEFAULT_HUB_CAPACITY: usize = 1024;
+
+/// Shortest and longest wait between two reconnection attempts.
+const MIN_RECONNECT_DELAY: Duration = Duration::from_millis(100);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(10);
+
+/// Pub/Sub connection shared by any number of subscribers.
+///
+/// Each channel or pattern is subscribed once on the hub connection and
+/// reference counted, dropping its last [`Subscription`] unsubscribes it.
+/// Messages are fanned out to the subscriptions through a broadcast channel and
+/// everything is subscribed again after the hub reconnects.
+#[derive(Clone)]
+pub struct SubscriptionHub {
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl SubscriptionHub {
+    /// Opens the hub connection and spawns the task driving it.
+    pub async fn connect(client: Client) -> Result<Self, RedisPoolError> {
+        Self::connect_with_capacity(client, DEFAULT_HUB_CAPACITY).await
+    }
+
+    /// Like [`connect`](Self::connect), buffering up to `capacity` messages per
+    /// channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub async fn connect_with_capacity(
+        client: Client,
+        capacity: usize,
+    ) -> Result<Self, RedisPoolError> {
+        assert!(capacity > 0, "subscription hub capacity must be non zero");
+
+        let (sink, stream) = client.get_async_pubsub().await?.split();
+        let (commands, receiver) = mpsc::unbounded_channel();
+        let hub = Hub {
+            client,
+            capacity,
+            commands: receiver,
+            channels: HashMap::new(),
+            patterns: HashMap::new(),
+        };
+
+        rt::spawn(hub.run(sink, stream));
+        Ok(SubscriptionHub { commands })
+    }
+
+    /// Subscribes to `channel`, only sending `SUBSCRIBE` for its first subscription.
+    pub async fn subscribe(
+        &self,
+        channel: impl Into<String>,
+    ) -> Result<Subscription, RedisPoolError> {
+        self.acquire(Topic::Channel(channel.into())).await
+    }
+
+    /// Subscribes to `pattern`, only sending `PSUBSCRIBE` for its first subscription.
+    pub async fn psubscribe(
+        &self,
+        pattern: impl Into<String>,
+    ) -> Result<Subscription, RedisPoolError> {
+        self.acquire(Topic::Pattern(pattern.into())).await
+    }
+
+    async fn acquire(&self, topic: Topic) -> Result<Subscription, RedisPoolError> {
+        let (reply, receiver) = oneshot::channel();
+
+        self.commands
+            .send(Command::Subscribe {
+                topic: topic.clone(),
+                reply,
+            })
+            .map_err(|_| RedisPoolError::Closed)?;
+
+        let rx = receiver.await.map_err(|_| RedisPoolError::Closed)??;
+
+        Ok(Subscription {
+            rx,
+            topic,
+            commands: self.commands.clone(),
+        })
+    }
+}
+
+/// Receiver for the messages of one channel or pattern of a [`SubscriptionHub`].
+pub struct Subscription {
+    rx: broadcast::Receiver<Msg>,
+    topic: Topic,
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+impl Subscription {
+    /// Waits for the next message, returning [`RecvError::Lagged`] if this
+    /// subscription fell more than the hub capacity behind.
+    pub async fn recv(&mut self) -> Result<Msg, RecvError> {
+        self.rx.recv().await
+    }
+
+    /// Spawns a task sending the messages into `tx` until its receiver is dropped.
+    pub fn forward(mut self, tx: mpsc::Sender<Msg>) -> Task {
+        rt::spawn(async move {
+            loop {
+                let msg = tokio::select! {
+                    msg = self.rx.recv() => msg,
+                    _ = tx.closed() => return,
+                };
+
+                match msg {
+                    Ok(msg) => {
+                        if tx.send(msg).await.is_err() {
+                            return;
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("redis subscription skipped {} messages", skipped);
+                    }
+                    Err(RecvError::Closed) => return,
+                }
+            }
+        })
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let _ = self.commands.send(Command::Unsubscribe(self.topic.clone()));
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Topic {
+    Channel(String),
+    Pattern(String),
+}
+
+enum Command {
+    Subscribe {
+        topic: Topic,
+        reply: oneshot::Sender<RedisResult<broadcast::Receiver<Msg>>>,
+    },
+    Unsubscribe(Topic),
+}
+
+struct Entry {
+    tx: broadcast::Sender<Msg>,
+    subscriptions: usize,
+}
+
+/// Task owning the hub connection and the subscribed channels, which stops once
+/// the hub and all of its subscriptions are dropped.
+struct Hub {
+    client: Client,
+    capacity: usize,
+    commands: mpsc::UnboundedReceiver<Command>,
+    channels: HashMap<String, Entry>,
+    patterns: HashMap<String, Entry>,
+}
+
+impl Hub {
+    async fn run(mut self, sink: PubSubSink, stream: PubSubStream) {
+        let mut con = Some((sink, stream));
+        let mut delay = MIN_RECONNECT_DELAY;
+
+        loop {
+            let (sink, stream) = match con.take() {
+                Some(con) => con,
+                None => match self.reconnect().await {
+                    Ok(con) => {
+                        delay = MIN_RECONNECT_DELAY;
+                        con
+                    }
+                    Err(e) => {
+                        tracing::warn!("failed to reconnect redis subscription hub: {}", e);
+
+                        if !self.wait(delay).await {
+                            return;
+                        }
+
+                        delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                        continue;
+                    }
+                },
+            };
+
+            if !self.serve(sink, stream).await {
+                return;
+            }
+
+            tracing::warn!("redis subscription hub lost its connection, reconnecting");
+        }
+    }
+
+    /// Opens a new connection and subscribes again to every channel and pattern.
+    async fn reconnect(&self) -> RedisResult<(PubSubSink, PubSubStream)> {
+        let (mut sink, stream) = self.client.get_async_pubsub().await?.split();
+
+        if !self.channels.is_empty() {
+            sink.subscribe(self.channels.keys().map(String::as_str).collect::<Vec<_>>())
+                .await?;
+        }
+
+        if !self.patterns.is_empty() {
+            sink.psubscribe(self.patterns.keys().map(String::as_str).collect::<Vec<_>>())
+                .await?;
+        }
+
+        Ok((sink, stream))
+    }
+
+    /// Handles commands and messages until the connection drops, returns false
+    /// once there is nobody left to serve.
+    async fn serve(&mut self, mut sink: PubSubSink, mut stream: PubSubStream) -> bool {
+        loop {
+            tokio::select! {
+                command = self.commands.recv() => match command {
+                    Some(command) => self.handle(command, Some(&mut sink)).await,
+                    None => return false,
+                },
+                msg = stream.next() => match msg {
+                    Some(msg) => self.dispatch(msg),
+                    None => return true,
+                },
+            }
+        }
+    }
+
+    /// Waits `delay` before reconnecting while still handling commands, the
+    /// channels subscribed meanwhile are picked up by the next reconnection.
+    async fn wait(&mut self, delay: Duration) -> bool {
+        let mut sleep = pin!(rt::sleep(delay));
+
+        loop {
+            tokio::select! {
+                _ = &mut sleep => return true,
+                command = self.commands.recv() => match command {
+                    Some(command) => self.handle(command, None).await,
+                    None => return false,
+                },
+            }
+        }
+    }
+
+    async fn handle(&mut self, command: Command, mut sink: Option<&mut PubSubSink>) {
+        match command {
+            Command::Subscribe { topic, reply } => {
+                let res = self.subscribe(topic.clone(), sink.as_deref_mut()).await;
+
+                // The caller gave up waiting, so nobody will drop the subscription.
+                if let Err(Ok(_)) = reply.send(res) {
+                    self.unsubscribe(topic, sink).await;
+                }
+            }
+            Command::Unsubscribe(topic) => self.unsubscribe(topic, sink).await,
+        }
+    }
+
+    async fn subscribe(
+        &mut self,
+        topic: Topic,
+        sink: Option<&mut PubSubSink>,
+    ) -> RedisResult<broadcast::Receiver<Msg>> {
+        let capacity = self.capacity;
+        let (entries, name, pattern) = match topic {
+            Topic::Channel(name) => (&mut self.channels, name, false),
+            Topic::Pattern(name) => (&mut self.patterns, name, true),
+        };
+
+        if let Some(entry) = entries.get_mut(&name) {
+            entry.subscriptions += 1;
+            return Ok(entry.tx.subscribe());
+        }
+
+        if let Some(sink) = sink {
+            if pattern {
+                sink.psubscribe(&name).await?;
+            } else {
+                sink.subscribe(&name).await?;
+            }
+        }
+
+        let (tx, rx) = broadcast::channel(capacity);
+        entries.insert(
+            name,
+            Entry {
+                tx,
+                subscriptions: 1,
+            },
+        );
+
+        Ok(rx)
+    }
+
+    async fn unsubscribe(&mut self, topic: Topic, sink: Option<&mut PubSubSink>) {
+        let (entries, name, pattern) = match topic {
+            Topic::Channel(name) => (&mut self.channels, name, false),
+            Topic::Pattern(name) => (&mut self.patterns, name, true),
+        };
+
+        let Some(entry) = entries.get_mut(&name) else {
+            return;
+        };
+
+        entry.subscriptions -= 1;
+
+        if entry.subscriptions > 0 {
+            return;
+        }
+
+        entries.remove(&name);
+
+        if let Some(sink) = sink {
+            let res = if pattern {
+                sink.punsubscribe(&name).await
+            } else {
+                sink.unsubscribe(&name).await
+            };
+
+            if let Err(e) = res {
+                tracing::debug!("failed to unsubscribe from {}: {}", name, e);
+            }
+        }
+    }
+
+    fn dispatch(&self, msg: Msg) {
+        let entry = if msg.from_pattern() {
+            msg.get_pattern::<String>()
+                .ok()
+                .and_then(|pattern| self.patterns.get(&pattern))
+        } else {
+            self.channels.get(msg.get_channel_name())
+        };
+
+        if let Some(entry) = entry {
+            let _ = entry.tx.send(msg);
+        }
+    }
+}
@@ -1,16 +1,49 @@
 #![allow(dead_code)]
 #![doc = include_str!("../README.md")]
 
+pub mod blocking;
+pub mod builder;
 pub mod connection;
 pub mod errors;
 pub mod factory;
+pub mod failover;
+pub mod health;
+pub mod hooks;
+pub mod hub;
+mod maintenance;
+pub mod monitor;
+pub mod permit;
 pub mod pool;
+#[cfg(feature = "metrics")]
+mod recorder;
+pub mod replicated;
+pub mod reset;
+pub mod retry;
+pub mod rt;
+pub mod sharded;
+pub mod stats;
+mod tenant;
 
+pub use blocking::BlockingRedisPool;
+pub use blocking::SingleBlockingRedisPool;
+pub use builder::RedisPoolBuilder;
+pub use hub::SubscriptionHub;
+pub use permit::Priority;
 pub use pool::RedisPool;
 pub use pool::SingleRedisPool;
+pub use replicated::ReplicatedRedisPool;
+pub use reset::ResetPolicy;
+pub use sharded::ShardedRedisPool;
+pub use stats::PoolStatus;
 
 #[cfg(feature = "cluster")]
 pub mod cluster;
 
 #[cfg(feature = "cluster")]
 pub use cluster::ClusterRedisPool;
+
+#[cfg(feature = "sentinel")]
+pub mod sentinel;
+
+#[cfg(feature = "sentinel")]
+pub use sentinel::SentinelRedisPool;
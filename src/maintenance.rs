@@ -0,0 +1,160 @@
+use std::{
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+use crossbeam_queue::ArrayQueue;
+use tokio::sync::Notify;
+
+use crate::{
+    builder::PoolConfig,
+    connection::IdleConnection,
+    factory::ConnectionFactory,
+    permit::PermitQueue,
+    pool::PoolShared,
+    rt::{self, Task},
+};
+
+/// Longest time between two maintenance runs.
+const MAX_MAINTENANCE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Handle to the background task, which is aborted once the last pool clone
+/// holding it is dropped.
+pub(crate) struct TaskGuard {
+    handle: Task,
+    wake: Arc<Notify>,
+}
+
+impl TaskGuard {
+    /// Runs the task right away, used after connections were evicted.
+    pub(crate) fn wake(&self) {
+        self.wake.notify_one();
+    }
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Spawns a task which closes connections idle for longer than the idle timeout
+/// and refills the queue up to the configured minimum of idle connections.
+pub(crate) fn spawn_maintenance<F, C>(
+    factory: F,
+    config: Arc<PoolConfig>,
+    shared: &Arc<PoolShared<C>>,
+    permits: Option<Arc<PermitQueue>>,
+) -> TaskGuard
+where
+    F: ConnectionFactory<C> + Send + Sync + 'static,
+    C: redis::aio::ConnectionLike + Send + 'static,
+{
+    let wake = Arc::new(Notify::new());
+    let task = Maintenance {
+        factory,
+        config,
+        shared: Arc::downgrade(shared),
+        permits,
+        wake: wake.clone(),
+    };
+
+    TaskGuard {
+        handle: rt::spawn(task.run()),
+        wake,
+    }
+}
+
+struct Maintenance<F, C> {
+    factory: F,
+    config: Arc<PoolConfig>,
+    shared: Weak<PoolShared<C>>,
+    permits: Option<Arc<PermitQueue>>,
+    wake: Arc<Notify>,
+}
+
+impl<F, C> Maintenance<F, C>
+where
+    F: ConnectionFactory<C> + Send + Sync,
+    C: redis::aio::ConnectionLike + Send,
+{
+    async fn run(self) {
+        let interval = self
+            .config
+            .idle_timeout
+            .map_or(MAX_MAINTENANCE_INTERVAL, |idle_timeout| {
+                idle_timeout.min(MAX_MAINTENANCE_INTERVAL)
+            });
+
+        loop {
+            let _ = rt::timeout(interval, self.wake.notified()).await;
+
+            let Some(shared) = self.shared.upgrade() else {
+                return;
+            };
+
+            if shared.is_closed() {
+                return;
+            }
+
+            if let Some(idle_timeout) = self.config.idle_timeout {
+                let reaped = reap(&shared.queue.read(), idle_timeout);
+
+                if reaped > 0 {
+                    tracing::debug!("closed {} idle redis connections", reaped);
+                }
+            }
+
+            self.replenish(&shared).await;
+            shared.stats.idle(shared.idle_count());
+        }
+    }
+
+    /// Creates connections until the queue holds the minimum of idle
+    /// connections, without going over the connection limit.
+    async fn replenish(&self, shared: &PoolShared<C>) {
+        while shared.idle_count() < self.config.min_idle
+            && !shared.is_closed()
+            && !self.at_con_limit(shared.idle_count())
+        {
+            match shared.create(&self.factory).await {
+                Ok(con) => {
+                    if !shared.push_idle(IdleConnection::new(con, self.config.lifetime_deadline()))
+                    {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("failed to refill redis pool: {}", e);
+                    return;
+                }
+            }
+        }
+    }
+
+    fn at_con_limit(&self, idle: usize) -> bool {
+        self.permits
+            .as_ref()
+            .is_some_and(|permits| permits.is_full(idle))
+    }
+}
+
+/// Cycles through the queue once, dropping the idle or expired connections and
+/// pushing the others back in their original order.
+fn reap<C>(queue: &ArrayQueue<IdleConnection<C>>, idle_timeout: Duration) -> usize {
+    let mut reaped = 0;
+
+    for _ in 0..queue.len() {
+        let Some(idle) = queue.pop() else {
+            break;
+        };
+
+        if idle.is_stale(Some(idle_timeout)) {
+            reaped += 1;
+        } else {
+            let _ = queue.push(idle);
+        }
+    }
+
+    reaped
+}
@@ -0,0 +1,136 @@
+use std::{
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use futures_util::{Stream, StreamExt};
+use redis::aio::{Monitor, MultiplexedConnection};
+
+use crate::{permit::PoolPermit, pool::PoolShared};
+
+/// Command seen by a `MONITOR` connection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorEvent {
+    /// Time the server processed the command.
+    pub timestamp: SystemTime,
+    /// Database the command ran against.
+    pub db: u32,
+    /// Address of the client which sent the command, `lua` for scripts or
+    /// `unix:<path>` for unix sockets.
+    pub client: String,
+    /// Name of the command as it was sent.
+    pub command: String,
+    /// Arguments of the command, with the server escaping undone.
+    pub args: Vec<Vec<u8>>,
+}
+
+impl MonitorEvent {
+    /// Parses a line sent by `MONITOR`, such as
+    /// `1339518083.107412 [0 127.0.0.1:60866] "keys" "*"`.
+    pub fn parse(line: &str) -> Option<Self> {
+        let (timestamp, rest) = line.split_once(' ')?;
+        let timestamp = Duration::try_from_secs_f64(timestamp.parse().ok()?).ok()?;
+        let (source, rest) = rest.strip_prefix('[')?.split_once("] ")?;
+        let (db, client) = source.split_once(' ')?;
+        let mut words = parse_words(rest.as_bytes())?.into_iter();
+        let command = String::from_utf8(words.next()?).ok()?;
+
+        Some(MonitorEvent {
+            timestamp: UNIX_EPOCH + timestamp,
+            db: db.parse().ok()?,
+            client: client.to_owned(),
+            command,
+            args: words.collect(),
+        })
+    }
+}
+
+/// Splits the quoted and escaped words of a monitor line.
+fn parse_words(line: &[u8]) -> Option<Vec<Vec<u8>>> {
+    let mut words = Vec::new();
+    let mut bytes = line.iter().copied();
+
+    loop {
+        match bytes.next() {
+            None => return Some(words),
+            Some(b' ') => continue,
+            Some(b'"') => {}
+            Some(_) => return None,
+        }
+
+        let mut word = Vec::new();
+
+        loop {
+            match bytes.next()? {
+                b'"' => break,
+                b'\\' => word.push(match bytes.next()? {
+                    b'n' => b'\n',
+                    b'r' => b'\r',
+                    b't' => b'\t',
+                    b'a' => 0x07,
+                    b'b' => 0x08,
+                    b'x' => {
+                        let hex = [bytes.next()?, bytes.next()?];
+                        u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?
+                    }
+                    other => other,
+                }),
+                byte => word.push(byte),
+            }
+        }
+
+        words.push(word);
+    }
+}
+
+/// `MONITOR` connection created by [`SingleRedisPool::monitor`](crate::SingleRedisPool::monitor).
+///
+/// It is a [`Stream`] of the commands processed by the server and holds a
+/// connection permit of the pool until it is dropped.
+pub struct RedisPoolMonitor {
+    stream: Box<dyn Stream<Item = String> + Send + Unpin>,
+    permit: Option<PoolPermit>,
+    shared: Arc<PoolShared<MultiplexedConnection>>,
+    checked_out_at: Instant,
+}
+
+impl RedisPoolMonitor {
+    pub(crate) fn new(
+        monitor: Monitor,
+        permit: Option<PoolPermit>,
+        shared: Arc<PoolShared<MultiplexedConnection>>,
+    ) -> Self {
+        RedisPoolMonitor {
+            stream: Box::new(monitor.into_on_message::<String>()),
+            permit,
+            shared,
+            checked_out_at: Instant::now(),
+        }
+    }
+}
+
+impl Stream for RedisPoolMonitor {
+    type Item = MonitorEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            let Some(line) = std::task::ready!(self.stream.poll_next_unpin(cx)) else {
+                return Poll::Ready(None);
+            };
+
+            match MonitorEvent::parse(&line) {
+                Some(event) => return Poll::Ready(Some(event)),
+                None => tracing::debug!("skipped unexpected monitor line: {}", line),
+            }
+        }
+    }
+}
+
+impl Drop for RedisPoolMonitor {
+    fn drop(&mut self) {
+        self.permit.take();
+        self.shared.checked_in(self.checked_out_at.elapsed());
+    }
+}
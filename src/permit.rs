@@ -0,0 +1,316 @@
+use std::{
+    cmp::Reverse,
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use tokio::sync::{oneshot, OwnedSemaphorePermit, Semaphore, TryAcquireError};
+
+use crate::errors::RedisPoolError;
+
+/// Wait after which a waiter is served as if it had the next higher priority.
+pub const DEFAULT_PRIORITY_AGING: Duration = Duration::from_millis(500);
+
+/// Order in which tasks waiting for a connection permit are served, see
+/// [`RedisPool::acquire_with_priority`](crate::RedisPool::acquire_with_priority).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Background work which can wait behind everything else.
+    Low,
+    /// Priority of [`RedisPool::acquire`](crate::RedisPool::acquire).
+    #[default]
+    Normal,
+    /// Latency critical callers, served before any other waiter.
+    High,
+}
+
+impl Priority {
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Connection limit of a pool, handing released permits straight to the
+/// waiter with the highest priority.
+///
+/// Waiters of the same priority are served in order of arrival, and every
+/// `aging` spent waiting counts as one more priority so low priority waiters
+/// are not starved by a steady flow of high priority ones.
+///
+/// The limit can be changed at any time. Lowering it below the permits in use
+/// forgets the extra permits as they are released.
+///
+/// Waiters which give up stay queued until they reach the front of their
+/// queue, only the count of the remaining ones is kept up to date.
+pub(crate) struct PermitQueue {
+    sem: Arc<Semaphore>,
+    limit: AtomicUsize,
+    /// Permits in use to forget once released, after the limit was lowered.
+    debt: AtomicUsize,
+    aging: Duration,
+    waiters: Mutex<[VecDeque<Waiter>; 3]>,
+    /// Queued waiters neither served nor given up, only incremented with the
+    /// waiters locked.
+    pending: AtomicUsize,
+}
+
+struct Waiter {
+    since: Instant,
+    tx: oneshot::Sender<PoolPermit>,
+    /// Set by whoever takes the waiter out of `pending` first, serving it or
+    /// giving up.
+    claimed: Arc<AtomicBool>,
+}
+
+impl PermitQueue {
+    pub(crate) fn new(permits: usize, aging: Duration) -> Arc<Self> {
+        Arc::new(PermitQueue {
+            sem: Arc::new(Semaphore::new(permits)),
+            limit: AtomicUsize::new(permits),
+            debt: AtomicUsize::new(0),
+            aging,
+            waiters: Mutex::new(Default::default()),
+            pending: AtomicUsize::new(0),
+        })
+    }
+
+    /// Takes a permit if one is free and nobody is waiting for it.
+    pub(crate) fn try_acquire(self: &Arc<Self>) -> Result<PoolPermit, RedisPoolError> {
+        let _waiters = self.waiters.lock();
+
+        if self.has_pending() {
+            return Err(RedisPoolError::PoolExhausted);
+        }
+
+        match self.sem.clone().try_acquire_owned() {
+            Ok(permit) => Ok(PoolPermit::new(permit, self.clone())),
+            Err(TryAcquireError::NoPermits) => Err(RedisPoolError::PoolExhausted),
+            Err(TryAcquireError::Closed) => Err(RedisPoolError::Closed),
+        }
+    }
+
+    /// Waits for a permit behind the waiters of the same or a higher priority.
+    pub(crate) async fn acquire(
+        self: &Arc<Self>,
+        priority: Priority,
+    ) -> Result<PoolPermit, RedisPoolError> {
+        let (rx, _queued) = {
+            let mut waiters = self.waiters.lock();
+
+            if !self.has_pending() {
+                match self.sem.clone().try_acquire_owned() {
+                    Ok(permit) => return Ok(PoolPermit::new(permit, self.clone())),
+                    Err(TryAcquireError::Closed) => return Err(RedisPoolError::Closed),
+                    Err(TryAcquireError::NoPermits) => {}
+                }
+            }
+
+            let (tx, rx) = oneshot::channel();
+            let claimed = Arc::new(AtomicBool::new(false));
+            waiters[priority.index()].push_back(Waiter {
+                since: Instant::now(),
+                tx,
+                claimed: claimed.clone(),
+            });
+            self.pending.fetch_add(1, Ordering::SeqCst);
+
+            (
+                rx,
+                Queued {
+                    claimed,
+                    pending: &self.pending,
+                },
+            )
+        };
+
+        rx.await.map_err(|_| RedisPoolError::Closed)
+    }
+
+    fn has_pending(&self) -> bool {
+        self.pending.load(Ordering::SeqCst) > 0
+    }
+
+    /// Hands a released permit to the next waiter, or back to the semaphore if
+    /// there is none.
+    fn release(self: &Arc<Self>, permit: OwnedSemaphorePermit) {
+        if take_one(&self.debt) {
+            permit.forget();
+            return;
+        }
+
+        let mut waiters = self.waiters.lock();
+        let mut permit = PoolPermit::new(permit, self.clone());
+
+        while let Some(waiter) = self.next(&mut waiters) {
+            match waiter.tx.send(permit) {
+                Ok(()) => return,
+                // The waiter gave up in the meantime.
+                Err(returned) => permit = returned,
+            }
+        }
+
+        permit.permit.take();
+    }
+
+    /// Pops the waiter with the highest priority once aged, the oldest first,
+    /// dropping the waiters which gave up on the way.
+    fn next(&self, waiters: &mut [VecDeque<Waiter>; 3]) -> Option<Waiter> {
+        loop {
+            let now = Instant::now();
+            let aging = self.aging.as_nanos().max(1);
+
+            let index = (0..waiters.len())
+                .filter_map(|index| {
+                    let queue = &mut waiters[index];
+
+                    while queue.front()?.claimed.load(Ordering::SeqCst) {
+                        queue.pop_front();
+                    }
+
+                    let since = queue.front()?.since;
+                    let promotions = now.duration_since(since).as_nanos() / aging;
+                    let priority = (index as u128 + promotions).min(Priority::High.index() as u128);
+
+                    Some((index, priority, Reverse(since)))
+                })
+                .max_by_key(|&(_, priority, since)| (priority, since))?
+                .0;
+
+            let waiter = waiters[index].pop_front()?;
+
+            // It may have given up since it was checked.
+            if claim(&waiter.claimed, &self.pending) {
+                return Some(waiter);
+            }
+        }
+    }
+
+    /// Number of tasks waiting with `priority`.
+    pub(crate) fn waiters(&self, priority: Priority) -> usize {
+        self.waiters.lock()[priority.index()]
+            .iter()
+            .filter(|waiter| !waiter.claimed.load(Ordering::SeqCst))
+            .count()
+    }
+
+    pub(crate) fn limit(&self) -> usize {
+        self.limit.load(Ordering::SeqCst)
+    }
+
+    /// Number of permits currently held.
+    pub(crate) fn in_use(&self) -> usize {
+        (self.limit() + self.debt.load(Ordering::SeqCst))
+            .saturating_sub(self.sem.available_permits())
+    }
+
+    /// Whether `idle` queued connections and the permits in use together
+    /// reach the limit, so no more connections should be opened.
+    pub(crate) fn is_full(&self, idle: usize) -> bool {
+        self.in_use() + idle >= self.limit()
+    }
+
+    /// Changes the limit, handing new permits to the waiters right away.
+    pub(crate) fn resize(self: &Arc<Self>, limit: usize) {
+        let previous = self.limit.swap(limit, Ordering::SeqCst);
+
+        if limit < previous {
+            let excess = previous - limit;
+            let forgotten = self.sem.forget_permits(excess);
+            self.debt.fetch_add(excess - forgotten, Ordering::SeqCst);
+            return;
+        }
+
+        let added = limit - previous;
+        let paid = self
+            .debt
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |debt| {
+                Some(debt.saturating_sub(added))
+            })
+            .unwrap_or_default()
+            .min(added);
+
+        self.sem.add_permits(added - paid);
+
+        // Permits only reach the waiters through `release`.
+        loop {
+            let permit = {
+                let _waiters = self.waiters.lock();
+
+                if !self.has_pending() {
+                    return;
+                }
+
+                match self.sem.clone().try_acquire_owned() {
+                    Ok(permit) => permit,
+                    Err(_) => return,
+                }
+            };
+
+            self.release(permit);
+        }
+    }
+
+    /// Fails every current and future waiter with [`RedisPoolError::Closed`].
+    pub(crate) fn close(&self) {
+        self.sem.close();
+        self.waiters.lock().iter_mut().for_each(VecDeque::clear);
+    }
+}
+
+/// Decrements `counter` unless it is zero, returning whether it did.
+fn take_one(counter: &AtomicUsize) -> bool {
+    counter
+        .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+        .is_ok()
+}
+
+/// Takes a waiter out of `pending` unless it already was, returning whether
+/// this call did.
+fn claim(claimed: &AtomicBool, pending: &AtomicUsize) -> bool {
+    if claimed.swap(true, Ordering::SeqCst) {
+        return false;
+    }
+
+    pending.fetch_sub(1, Ordering::SeqCst);
+    true
+}
+
+/// Claims a queued waiter once its acquire is dropped, unless it was served.
+struct Queued<'a> {
+    claimed: Arc<AtomicBool>,
+    pending: &'a AtomicUsize,
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        claim(&self.claimed, self.pending);
+    }
+}
+
+/// Connection permit of a pool, handed to the next waiter once dropped.
+pub(crate) struct PoolPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    queue: Arc<PermitQueue>,
+}
+
+impl PoolPermit {
+    fn new(permit: OwnedSemaphorePermit, queue: Arc<PermitQueue>) -> Self {
+        PoolPermit {
+            permit: Some(permit),
+            queue,
+        }
+    }
+}
+
+impl Drop for PoolPermit {
+    fn drop(&mut self) {
+        if let Some(permit) = self.permit.take() {
+            self.queue.release(permit);
+        }
+    }
+}
@@ -1,11 +1,46 @@
-use crate::{connection::RedisPoolConnection, errors::RedisPoolError, factory::ConnectionFactory};
+use crate::{
+    builder::{PoolConfig, RedisPoolBuilder},
+    connection::{IdleConnection, RedisPoolConnection, RedisPoolPubSub},
+    errors::{ConfigError, RedisPoolError},
+    factory::ConnectionFactory,
+    health::{HealthCheck, PingCheck},
+    hooks::{run_hook, PoolHooks},
+    hub::SubscriptionHub,
+    maintenance::{spawn_maintenance, TaskGuard},
+    monitor::RedisPoolMonitor,
+    permit::{PermitQueue, PoolPermit, Priority},
+    reset::ResetPolicy,
+    retry::{CircuitBreaker, RetryPolicy},
+    rt,
+    stats::{PoolStats, PoolStatus, TenantStatus},
+    tenant::TenantQuotas,
+};
 use crossbeam_queue::ArrayQueue;
-use redis::{aio::MultiplexedConnection, Client, RedisResult};
-use std::{ops::Deref, sync::Arc};
-use tokio::sync::Semaphore;
+use parking_lot::{Mutex, RwLock};
+use redis::{aio::MultiplexedConnection, Client};
+use std::{
+    future::Future,
+    ops::Deref,
+    pin::pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::Notify;
 
 pub const DEFAULT_POOL_SIZE: usize = 16;
 pub const DEFAULT_CON_LIMIT: usize = 512;
+/// Name of the pools created without [`RedisPoolBuilder::name`], including
+/// those of [`RedisPool::new`] and the `From` conversions.
+///
+/// Pools sharing a name share their metrics, so the idle gauge only shows the
+/// last update of either pool while the other gauges add up. Build the pools of
+/// a [`ReplicatedRedisPool`](crate::ReplicatedRedisPool) or
+/// [`ShardedRedisPool`](crate::ShardedRedisPool) with distinct names, with
+/// the `metrics` feature a warning is logged when live pools share a name.
+pub const DEFAULT_POOL_NAME: &str = "default";
 
 pub struct RedisPool<F, C>
 where
@@ -13,60 +48,544 @@ where
     C: redis::aio::ConnectionLike + Send,
 {
     factory: F,
-    queue: Arc<ArrayQueue<C>>,
-    sem: Option<Arc<Semaphore>>,
+    config: Arc<PoolConfig>,
+    shared: Arc<PoolShared<C>>,
+    permits: Option<Arc<PermitQueue>>,
+    health_check: Arc<dyn HealthCheck<C>>,
+    maintenance: Option<Arc<TaskGuard>>,
 }
 
-impl<F, C> RedisPool<F, C>
+/// State shared between a pool, the connections it handed out and its
+/// background task.
+pub(crate) struct PoolShared<C> {
+    /// Swapped for a queue of another capacity when the pool is resized.
+    pub(crate) queue: RwLock<ArrayQueue<IdleConnection<C>>>,
+    pub(crate) stats: PoolStats,
+    pub(crate) hooks: PoolHooks<C>,
+    /// [`ConnectionFactory::is_reusable`] of the pool's factory.
+    reusable: Box<dyn Fn(&C) -> bool + Send + Sync>,
+    pub(crate) reset_policy: ResetPolicy,
+    tenants: TenantQuotas,
+    retry: RetryPolicy,
+    breaker: Option<CircuitBreaker>,
+    closed: AtomicBool,
+    released: Notify,
+    /// Held while resizing so concurrent resizes are applied one at a time.
+    resizing: Mutex<()>,
+}
+
+impl<C> PoolShared<C> {
+    fn new(
+        config: &PoolConfig,
+        hooks: PoolHooks<C>,
+        reusable: Box<dyn Fn(&C) -> bool + Send + Sync>,
+    ) -> Self {
+        PoolShared {
+            queue: RwLock::new(ArrayQueue::new(config.pool_size)),
+            stats: PoolStats::new(&config.name),
+            hooks,
+            reusable,
+            reset_policy: config.reset_policy,
+            tenants: TenantQuotas::new(config),
+            retry: config.retry,
+            breaker: config
+                .circuit_breaker
+                .map(|(failure_threshold, open_timeout)| {
+                    CircuitBreaker::new(failure_threshold, open_timeout)
+                }),
+            closed: AtomicBool::new(false),
+            released: Notify::new(),
+            resizing: Mutex::new(()),
+        }
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Whether the factory allows `con` to be handed out or queued again.
+    pub(crate) fn is_reusable(&self, con: &C) -> bool {
+        (self.reusable)(con)
+    }
+
+    /// Queues an idle connection, dropping it if the pool is closed or full.
+    /// Returns whether it was queued.
+    pub(crate) fn push_idle(&self, idle: IdleConnection<C>) -> bool {
+        if self.is_closed() {
+            return false;
+        }
+
+        let queue = self.queue.read();
+        let queued = queue.push(idle).is_ok();
+
+        if !queued {
+            self.stats.dropped_queue_full();
+        }
+
+        self.stats.idle(queue.len());
+        queued
+    }
+
+    pub(crate) fn pop_idle(&self) -> Option<IdleConnection<C>> {
+        self.queue.read().pop()
+    }
+
+    pub(crate) fn idle_count(&self) -> usize {
+        self.queue.read().len()
+    }
+
+    /// Moves the idle connections to a queue holding up to `pool_size`,
+    /// closing the ones which do not fit.
+    fn resize_queue(&self, pool_size: usize) {
+        let previous = std::mem::replace(&mut *self.queue.write(), ArrayQueue::new(pool_size));
+        let queue = self.queue.read();
+        let mut closed = 0;
+
+        while let Some(idle) = previous.pop() {
+            if queue.push(idle).is_err() {
+                closed += 1;
+            }
+        }
+
+        if closed > 0 {
+            tracing::debug!("closed {} idle redis connections after resizing", closed);
+        }
+
+        self.stats.idle(queue.len());
+    }
+
+    /// Records a connection leaving the caller's hands, waking up `close`.
+    pub(crate) fn checked_in(&self, held: Duration) {
+        self.stats.checked_in(held);
+        self.released.notify_waiters();
+    }
+
+    fn drain(&self) {
+        while self.pop_idle().is_some() {}
+        self.stats.idle(0);
+    }
+}
+
+impl<C> PoolShared<C>
 where
-    F: ConnectionFactory<C> + Send + Sync + Clone,
     C: redis::aio::ConnectionLike + Send,
+{
+    /// Creates a connection, retrying as configured, and runs the
+    /// `after_connect` hook on it.
+    pub(crate) async fn create<F>(&self, factory: &F) -> Result<C, RedisPoolError>
+    where
+        F: ConnectionFactory<C>,
+    {
+        let mut failures = 0;
+
+        let mut con = loop {
+            if self
+                .breaker
+                .as_ref()
+                .is_some_and(|breaker| !breaker.allow())
+            {
+                return Err(RedisPoolError::CircuitOpen);
+            }
+
+            let started = Instant::now();
+
+            match factory.create().await {
+                Ok(con) => {
+                    self.stats.created(started.elapsed());
+
+                    if let Some(breaker) = &self.breaker {
+                        breaker.succeeded();
+                    }
+
+                    break con;
+                }
+                Err(e) => {
+                    if let Some(breaker) = &self.breaker {
+                        breaker.failed();
+                    }
+
+                    failures += 1;
+
+                    if failures >= self.retry.max_attempts() {
+                        return Err(e.into());
+                    }
+
+                    let backoff = self.retry.backoff(failures);
+                    tracing::debug!(
+                        "failed to create redis connection, retrying in {:?}: {}",
+                        backoff,
+                        e
+                    );
+                    rt::sleep(backoff).await;
+                }
+            }
+        };
+
+        run_hook(&self.hooks.after_connect, &mut con).await?;
+        Ok(con)
+    }
+}
+
+impl<F, C> RedisPool<F, C>
+where
+    F: ConnectionFactory<C> + Send + Sync + Clone + 'static,
+    C: redis::aio::ConnectionLike + Send + 'static,
 {
     pub fn new(factory: F, pool_size: usize, con_limit: Option<usize>) -> Self {
         if pool_size > con_limit.unwrap_or(usize::MAX) {
             tracing::warn!("pool size is greater then connection limit");
         }
 
+        Self::with_config(
+            factory,
+            PoolConfig {
+                pool_size,
+                con_limit,
+                ..PoolConfig::default()
+            },
+            Arc::new(PingCheck),
+            PoolHooks::default(),
+        )
+    }
+
+    /// Creates a [`RedisPoolBuilder`] which validates the configuration on build.
+    pub fn builder(factory: F) -> RedisPoolBuilder<F, C> {
+        RedisPoolBuilder::new(factory)
+    }
+
+    pub(crate) fn with_config(
+        factory: F,
+        config: PoolConfig,
+        health_check: Arc<dyn HealthCheck<C>>,
+        hooks: PoolHooks<C>,
+    ) -> Self {
+        let config = Arc::new(config);
+        let reusable = {
+            let factory = factory.clone();
+            Box::new(move |con: &C| factory.is_reusable(con))
+        };
+        let shared = Arc::new(PoolShared::new(&config, hooks, reusable));
+        let permits = config
+            .con_limit
+            .map(|lim| PermitQueue::new(lim, config.priority_aging));
+        let maintenance = config.needs_maintenance().then(|| {
+            Arc::new(spawn_maintenance(
+                factory.clone(),
+                config.clone(),
+                &shared,
+                permits.clone(),
+            ))
+        });
+
         RedisPool {
             factory,
-            queue: Arc::new(ArrayQueue::new(pool_size)),
-            sem: con_limit.map(|lim| Arc::new(Semaphore::new(lim))),
+            config,
+            shared,
+            permits,
+            health_check,
+            maintenance,
         }
     }
 
+    /// Waits for a free connection, bounded by the configured acquire timeout if any.
     pub async fn acquire(&self) -> Result<RedisPoolConnection<C>, RedisPoolError> {
-        let permit = match &self.sem {
-            Some(sem) => Some(sem.clone().acquire_owned().await?),
+        self.acquire_with_priority(Priority::Normal).await
+    }
+
+    /// Like [`acquire`](Self::acquire), getting the next released connection
+    /// permit before the waiters of a lower `priority` once the connection
+    /// limit is reached.
+    pub async fn acquire_with_priority(
+        &self,
+        priority: Priority,
+    ) -> Result<RedisPoolConnection<C>, RedisPoolError> {
+        match self.config.acquire_timeout {
+            Some(timeout) => with_timeout(timeout, self.acquire_unbounded(priority)).await,
+            None => self.acquire_unbounded(priority).await,
+        }
+    }
+
+    /// Waits at most `timeout` for a permit and a connection,
+    /// returning [`RedisPoolError::Timeout`] once it elapsed.
+    pub async fn acquire_with_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<RedisPoolConnection<C>, RedisPoolError> {
+        with_timeout(timeout, self.acquire_unbounded(Priority::Normal)).await
+    }
+
+    /// Like [`acquire`](Self::acquire), also waiting until `tenant` is below
+    /// its connection quota. The connection counts against that quota until
+    /// it is dropped, while idle connections are shared by every tenant.
+    ///
+    /// Tenants without a configured limit are only tracked while they have
+    /// connections or waiters, so per-request tenant names do not add up.
+    pub async fn acquire_for(
+        &self,
+        tenant: &str,
+    ) -> Result<RedisPoolConnection<C>, RedisPoolError> {
+        self.bounded(async {
+            self.ensure_open()?;
+
+            // The tenant quota comes first, so a tenant over its quota does
+            // not hold on to permits of the pool while waiting.
+            let tenant = self.shared.tenants.acquire(tenant).await?;
+            let mut con = self.acquire_unbounded(Priority::Normal).await?;
+
+            con.set_tenant(tenant);
+            Ok::<_, RedisPoolError>(con)
+        })
+        .await
+    }
+
+    /// Returns [`RedisPoolError::PoolExhausted`] instead of waiting when the
+    /// connection limit is reached.
+    pub async fn try_acquire(&self) -> Result<RedisPoolConnection<C>, RedisPoolError> {
+        let started = Instant::now();
+        let permit = match &self.permits {
+            Some(permits) => Some(permits.try_acquire()?),
             None => None,
         };
-        let con = self.acquire_connection().await?;
-        Ok(RedisPoolConnection::new(con, permit, self.queue.clone()))
-    }
-
-    async fn acquire_connection(&self) -> RedisResult<C> {
-        while let Some(mut con) = self.queue.pop() {
-            let res = redis::Pipeline::with_capacity(2)
-                .cmd("UNWATCH")
-                .ignore()
-                .cmd("PING")
-                .arg(1)
-                .query_async::<(usize,)>(&mut con)
-                .await;
-
-            match res {
-                Ok((1,)) => {
-                    return Ok(con);
-                }
-                Ok(_) => {
-                    tracing::warn!("connection ping returned wrong value");
+        self.ensure_open()?;
+
+        let (con, expires_at) = self.bounded(self.acquire_connection()).await?;
+        Ok(self.checkout(con, expires_at, permit, started))
+    }
+
+    async fn acquire_unbounded(
+        &self,
+        priority: Priority,
+    ) -> Result<RedisPoolConnection<C>, RedisPoolError> {
+        let started = Instant::now();
+        let permit = self.acquire_permit(priority).await?;
+        let (con, expires_at) = self.acquire_connection().await?;
+        Ok(self.checkout(con, expires_at, permit, started))
+    }
+
+    /// Waits for a connection permit if the pool has a connection limit.
+    pub(crate) async fn acquire_permit(
+        &self,
+        priority: Priority,
+    ) -> Result<Option<PoolPermit>, RedisPoolError> {
+        let permit = match &self.permits {
+            Some(permits) => {
+                let _waiting = self.shared.stats.waiting();
+                Some(permits.acquire(priority).await?)
+            }
+            None => None,
+        };
+
+        self.ensure_open()?;
+        Ok(permit)
+    }
+
+    /// Bounds `fut` by the configured acquire timeout, if any.
+    pub(crate) async fn bounded<T, E>(
+        &self,
+        fut: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, RedisPoolError>
+    where
+        RedisPoolError: From<E>,
+    {
+        match self.config.acquire_timeout {
+            Some(timeout) => with_timeout(timeout, fut).await,
+            None => fut.await.map_err(RedisPoolError::from),
+        }
+    }
+
+    fn checkout(
+        &self,
+        con: C,
+        expires_at: Option<Instant>,
+        permit: Option<PoolPermit>,
+        started: Instant,
+    ) -> RedisPoolConnection<C> {
+        self.shared.stats.checked_out(started.elapsed());
+        self.shared.stats.idle(self.shared.idle_count());
+
+        RedisPoolConnection::new(con, expires_at, permit, self.shared.clone())
+    }
+
+    fn ensure_open(&self) -> Result<(), RedisPoolError> {
+        if self.shared.is_closed() {
+            return Err(RedisPoolError::Closed);
+        }
+
+        Ok(())
+    }
+
+    /// Returns a healthy connection along with the end of its lifetime.
+    async fn acquire_connection(&self) -> Result<(C, Option<Instant>), RedisPoolError> {
+        while let Some(idle) = self.shared.pop_idle() {
+            if idle.is_stale(self.config.idle_timeout) {
+                self.wake_maintenance();
+                continue;
+            }
+
+            let IdleConnection {
+                mut con,
+                idle_since,
+                expires_at,
+            } = idle;
+
+            if !self.shared.is_reusable(&con) {
+                tracing::debug!("redis connection no longer reusable");
+                self.shared.stats.evicted_unhealthy();
+                self.wake_maintenance();
+                continue;
+            }
+
+            if let Err(e) = self
+                .health_check
+                .check(&mut con, idle_since.elapsed())
+                .await
+            {
+                tracing::warn!("bad redis connection: {}", e);
+                self.shared.stats.evicted_unhealthy();
+                self.wake_maintenance();
+                continue;
+            }
+
+            match run_hook(&self.shared.hooks.before_acquire, &mut con).await {
+                Ok(()) => {
+                    return Ok((con, expires_at));
                 }
                 Err(e) => {
-                    tracing::warn!("bad redis connection: {}", e);
+                    tracing::debug!("redis connection rejected by before_acquire: {}", e);
+                    self.wake_maintenance();
                 }
             }
         }
 
-        self.factory.create().await
+        let con = self.shared.create(&self.factory).await?;
+        Ok((con, self.config.lifetime_deadline()))
+    }
+
+    fn wake_maintenance(&self) {
+        if let Some(maintenance) = &self.maintenance {
+            maintenance.wake();
+        }
+    }
+
+    /// Fills the pool queue up to the configured minimum of idle connections
+    /// as long as the connection limit allows it, returning how many
+    /// connections were created.
+    pub async fn warm_up(&self) -> Result<usize, RedisPoolError> {
+        let mut created = 0;
+
+        while self.shared.idle_count() < self.config.min_idle {
+            self.ensure_open()?;
+
+            if self
+                .permits
+                .as_ref()
+                .is_some_and(|permits| permits.is_full(self.shared.idle_count()))
+            {
+                break;
+            }
+
+            let con = self.shared.create(&self.factory).await?;
+
+            if !self
+                .shared
+                .push_idle(IdleConnection::new(con, self.config.lifetime_deadline()))
+            {
+                break;
+            }
+
+            created += 1;
+        }
+
+        Ok(created)
+    }
+
+    /// Closes the pool for every clone.
+    ///
+    /// New acquires fail with [`RedisPoolError::Closed`], idle connections are
+    /// closed and connections still handed out are dropped instead of being
+    /// queued once returned. Waits up to `timeout` for those connections,
+    /// returning [`RedisPoolError::Timeout`] if some are still in use.
+    pub async fn close(&self, timeout: Duration) -> Result<(), RedisPoolError> {
+        self.shared.closed.store(true, Ordering::SeqCst);
+
+        if let Some(permits) = &self.permits {
+            permits.close();
+        }
+
+        self.shared.tenants.close();
+
+        self.shared.drain();
+
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let mut released = pin!(self.shared.released.notified());
+            released.as_mut().enable();
+
+            if self.shared.stats.in_use() == 0 {
+                break;
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+
+            if rt::timeout(remaining, released).await.is_none() {
+                self.shared.drain();
+                return Err(RedisPoolError::Timeout);
+            }
+        }
+
+        // Catches connections queued while closing.
+        self.shared.drain();
+
+        Ok(())
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.shared.is_closed()
+    }
+
+    /// Changes the pool size and connection limit of every clone of the pool.
+    ///
+    /// Idle connections move to a queue of the new size, closing the ones
+    /// which do not fit. Raising the limit hands the new permits to the
+    /// waiters right away, lowering it below the connections in use takes
+    /// effect as they are returned. A pool built without a connection limit
+    /// can not get one, and the other way around.
+    pub fn resize(&self, pool_size: usize, con_limit: Option<usize>) -> Result<(), ConfigError> {
+        if pool_size == 0 {
+            return Err(ConfigError::ZeroPoolSize);
+        }
+
+        if pool_size < self.config.min_idle {
+            return Err(ConfigError::MinIdleExceedsPoolSize {
+                min_idle: self.config.min_idle,
+                pool_size,
+            });
+        }
+
+        let permits = match (&self.permits, con_limit) {
+            (Some(_), Some(0)) => return Err(ConfigError::ZeroConLimit),
+            (Some(_), Some(con_limit)) if pool_size > con_limit => {
+                return Err(ConfigError::PoolSizeExceedsConLimit {
+                    pool_size,
+                    con_limit,
+                })
+            }
+            (Some(permits), Some(con_limit)) => Some((permits, con_limit)),
+            (None, None) => None,
+            _ => return Err(ConfigError::ToggledConLimit),
+        };
+
+        let _resizing = self.shared.resizing.lock();
+
+        self.shared.resize_queue(pool_size);
+
+        if let Some((permits, con_limit)) = permits {
+            permits.resize(con_limit);
+        }
+
+        self.wake_maintenance();
+        Ok(())
     }
 
     #[deprecated(since = "0.5.0", note = "Please use `acquire` instead")]
@@ -75,13 +594,70 @@ where
     }
 
     #[deprecated(since = "0.5.0", note = "Please use `acquire_connection` instead")]
-    async fn aquire_connection(&self) -> RedisResult<C> {
+    async fn aquire_connection(&self) -> Result<(C, Option<Instant>), RedisPoolError> {
         self.acquire_connection().await
     }
 
     pub fn factory(&self) -> &F {
         &self.factory
     }
+
+    pub fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    pub fn pool_size(&self) -> usize {
+        self.shared.queue.read().capacity()
+    }
+
+    pub fn con_limit(&self) -> Option<usize> {
+        self.permits.as_ref().map(|permits| permits.limit())
+    }
+
+    pub fn min_idle(&self) -> usize {
+        self.config.min_idle
+    }
+
+    /// Returns a snapshot of the pool's connection counts.
+    pub fn status(&self) -> PoolStatus {
+        self.shared
+            .stats
+            .snapshot(self.shared.idle_count(), self.con_limit())
+    }
+
+    /// Returns a snapshot of the connection counts of `tenant`.
+    pub fn tenant_status(&self, tenant: &str) -> TenantStatus {
+        self.shared.tenants.status(tenant)
+    }
+
+    /// Number of tasks waiting for a connection permit with `priority`.
+    pub fn waiters(&self, priority: Priority) -> usize {
+        self.permits
+            .as_ref()
+            .map_or(0, |permits| permits.waiters(priority))
+    }
+
+    /// Connections currently handed out, taken from the semaphore when the
+    /// pool has a connection limit.
+    pub(crate) fn in_use(&self) -> usize {
+        match &self.permits {
+            Some(permits) => permits.in_use(),
+            None => self.shared.stats.in_use(),
+        }
+    }
+}
+
+async fn with_timeout<T, E>(
+    timeout: Duration,
+    fut: impl Future<Output = Result<T, E>>,
+) -> Result<T, RedisPoolError>
+where
+    RedisPoolError: From<E>,
+{
+    match rt::timeout(timeout, fut).await {
+        Some(res) => res.map_err(RedisPoolError::from),
+        None => Err(RedisPoolError::Timeout),
+    }
 }
 
 impl<F, C> Clone for RedisPool<F, C>
@@ -92,8 +668,11 @@ where
     fn clone(&self) -> Self {
         RedisPool {
             factory: self.factory.clone(),
-            queue: self.queue.clone(),
-            sem: self.sem.clone(),
+            config: self.config.clone(),
+            shared: self.shared.clone(),
+            permits: self.permits.clone(),
+            health_check: self.health_check.clone(),
+            maintenance: self.maintenance.clone(),
         }
     }
 }
@@ -112,6 +691,43 @@ where
 
 pub type SingleRedisPool = RedisPool<Client, MultiplexedConnection>;
 
+impl SingleRedisPool {
+    /// Opens a dedicated Pub/Sub connection which holds one of the pool's
+    /// connection permits until it is dropped.
+    pub async fn pubsub(&self) -> Result<RedisPoolPubSub, RedisPoolError> {
+        self.bounded(async {
+            let started = Instant::now();
+            let permit = self.acquire_permit(Priority::Normal).await?;
+            let pubsub = self.factory.get_async_pubsub().await?;
+
+            self.shared.stats.checked_out(started.elapsed());
+            Ok::<_, RedisPoolError>(RedisPoolPubSub::new(pubsub, permit, self.shared.clone()))
+        })
+        .await
+    }
+
+    /// Opens a `MONITOR` connection streaming the commands processed by the
+    /// server, which holds one of the pool's connection permits until it is
+    /// dropped.
+    pub async fn monitor(&self) -> Result<RedisPoolMonitor, RedisPoolError> {
+        self.bounded(async {
+            let started = Instant::now();
+            let permit = self.acquire_permit(Priority::Normal).await?;
+            let monitor = self.factory.get_async_monitor().await?;
+
+            self.shared.stats.checked_out(started.elapsed());
+            Ok::<_, RedisPoolError>(RedisPoolMonitor::new(monitor, permit, self.shared.clone()))
+        })
+        .await
+    }
+
+    /// Connects a [`SubscriptionHub`] sharing one Pub/Sub connection of the
+    /// pool's client between any number of subscribers.
+    pub async fn subscription_hub(&self) -> Result<SubscriptionHub, RedisPoolError> {
+        SubscriptionHub::connect(self.factory.clone()).await
+    }
+}
+
 impl From<Client> for SingleRedisPool {
     fn from(value: Client) -> Self {
         RedisPool::new(value, DEFAULT_POOL_SIZE, Some(DEFAULT_CON_LIMIT))
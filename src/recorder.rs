@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+
+use parking_lot::Mutex;
+
+use metrics::{
+    counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram, Counter,
+    Gauge, Histogram, Unit,
+};
+
+const IDLE: &str = "redis_pool_idle_connections";
+const IN_USE: &str = "redis_pool_in_use_connections";
+const WAITERS: &str = "redis_pool_waiters";
+const DETACHED: &str = "redis_pool_detached_connections";
+const ACQUIRE: &str = "redis_pool_acquire_duration_seconds";
+const CHECKOUT: &str = "redis_pool_checkout_duration_seconds";
+const CREATE: &str = "redis_pool_create_duration_seconds";
+const CREATED: &str = "redis_pool_connections_created_total";
+const HEALTH_CHECK_FAILURES: &str = "redis_pool_health_check_failures_total";
+const QUEUE_FULL_DROPS: &str = "redis_pool_queue_full_drops_total";
+
+/// Number of live pools reporting under each name.
+static POOL_NAMES: Mutex<BTreeMap<String, usize>> = Mutex::new(BTreeMap::new());
+
+/// Handles to the metrics of one pool, all labelled with the pool name.
+pub(crate) struct PoolMetrics {
+    pool: String,
+    pub(crate) idle: Gauge,
+    pub(crate) in_use: Gauge,
+    pub(crate) waiters: Gauge,
+    pub(crate) detached: Gauge,
+    pub(crate) acquire: Histogram,
+    pub(crate) checkout: Histogram,
+    pub(crate) create: Histogram,
+    pub(crate) created: Counter,
+    pub(crate) health_check_failures: Counter,
+    pub(crate) queue_full_drops: Counter,
+}
+
+impl PoolMetrics {
+    pub(crate) fn new(pool: &str) -> Self {
+        describe();
+        register(pool);
+
+        let labels = [("pool", pool.to_owned())];
+
+        PoolMetrics {
+            pool: pool.to_owned(),
+            idle: gauge!(IDLE, &labels),
+            in_use: gauge!(IN_USE, &labels),
+            waiters: gauge!(WAITERS, &labels),
+            detached: gauge!(DETACHED, &labels),
+            acquire: histogram!(ACQUIRE, &labels),
+            checkout: histogram!(CHECKOUT, &labels),
+            create: histogram!(CREATE, &labels),
+            created: counter!(CREATED, &labels),
+            health_check_failures: counter!(HEALTH_CHECK_FAILURES, &labels),
+            queue_full_drops: counter!(QUEUE_FULL_DROPS, &labels),
+        }
+    }
+}
+
+impl Drop for PoolMetrics {
+    fn drop(&mut self) {
+        let mut names = POOL_NAMES.lock();
+
+        if let Some(count) = names.get_mut(&self.pool) {
+            *count -= 1;
+
+            if *count == 0 {
+                names.remove(&self.pool);
+            }
+        }
+    }
+}
+
+/// Counts a pool reporting under `pool`, warning when another live pool
+/// already does since their gauges would be mixed up.
+fn register(pool: &str) {
+    let mut names = POOL_NAMES.lock();
+    let count = names.entry(pool.to_owned()).or_default();
+    *count += 1;
+
+    if *count == 2 {
+        tracing::warn!(
+            "several redis pools report metrics as pool=\"{}\", give each a name with RedisPoolBuilder::name",
+            pool
+        );
+    }
+}
+
+fn describe() {
+    describe_gauge!(IDLE, "Connections waiting in the pool queue.");
+    describe_gauge!(IN_USE, "Connections currently handed out.");
+    describe_gauge!(WAITERS, "Tasks waiting for a connection permit.");
+    describe_gauge!(
+        DETACHED,
+        "Detached connections still holding a connection permit."
+    );
+    describe_histogram!(
+        ACQUIRE,
+        Unit::Seconds,
+        "Time taken to acquire a connection from the pool."
+    );
+    describe_histogram!(
+        CHECKOUT,
+        Unit::Seconds,
+        "Time a connection was held before returning to the pool."
+    );
+    describe_histogram!(
+        CREATE,
+        Unit::Seconds,
+        "Time taken by the factory to create a connection."
+    );
+    describe_counter!(CREATED, "Connections created by the factory.");
+    describe_counter!(
+        HEALTH_CHECK_FAILURES,
+        "Queued connections dropped because they failed the health check."
+    );
+    describe_counter!(
+        QUEUE_FULL_DROPS,
+        "Returned connections dropped because the pool queue was full."
+    );
+}
@@ -0,0 +1,154 @@
+use std::{
+    iter,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use futures_util::{future, FutureExt};
+use rand::Rng;
+use redis::aio::ConnectionLike;
+
+use crate::{
+    connection::RedisPoolConnection, errors::RedisPoolError, factory::ConnectionFactory,
+    pool::RedisPool,
+};
+
+/// Strategy picking the replica a read connection is acquired from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LoadBalancing {
+    /// Cycles through the replicas in order.
+    #[default]
+    RoundRobin,
+    /// Picks the replica with the fewest connections in use.
+    LeastInUse,
+    /// Picks a random replica.
+    Random,
+}
+
+/// Routes writes to a primary pool and reads to replica pools.
+///
+/// Reads try the replica chosen by the [`LoadBalancing`] strategy first, then
+/// the other replicas, and fall back to the primary once no replica could hand
+/// out a healthy connection right away.
+pub struct ReplicatedRedisPool<F, C>
+where
+    F: ConnectionFactory<C> + Send + Sync + Clone,
+    C: ConnectionLike + Send,
+{
+    primary: RedisPool<F, C>,
+    replicas: Arc<[RedisPool<F, C>]>,
+    load_balancing: LoadBalancing,
+    next: Arc<AtomicUsize>,
+}
+
+impl<F, C> ReplicatedRedisPool<F, C>
+where
+    F: ConnectionFactory<C> + Send + Sync + Clone + 'static,
+    C: ConnectionLike + Send + 'static,
+{
+    pub fn new(primary: RedisPool<F, C>, replicas: Vec<RedisPool<F, C>>) -> Self {
+        ReplicatedRedisPool {
+            primary,
+            replicas: replicas.into(),
+            load_balancing: LoadBalancing::default(),
+            next: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Sets the strategy picking the replica of each read, round-robin by default.
+    pub fn load_balancing(mut self, load_balancing: LoadBalancing) -> Self {
+        self.load_balancing = load_balancing;
+        self
+    }
+
+    /// Acquires a connection to the primary.
+    pub async fn acquire_write(&self) -> Result<RedisPoolConnection<C>, RedisPoolError> {
+        self.primary.acquire().await
+    }
+
+    /// Acquires a connection to a replica, or to the primary if every replica
+    /// failed to hand out a connection.
+    ///
+    /// Replicas at their connection limit are skipped, and only waited for
+    /// along with the primary once the primary is at its limit too.
+    pub async fn acquire_read(&self) -> Result<RedisPoolConnection<C>, RedisPoolError> {
+        let mut saturated = Vec::new();
+
+        for replica in self.replica_order() {
+            match self.replicas[replica].try_acquire().await {
+                Ok(con) => return Ok(con),
+                Err(RedisPoolError::PoolExhausted) => saturated.push(replica),
+                Err(e) => {
+                    tracing::debug!("redis replica {} unavailable: {}", replica, e);
+                }
+            }
+        }
+
+        if saturated.is_empty() {
+            return self.primary.acquire().await;
+        }
+
+        match self.primary.try_acquire().await {
+            Ok(con) => return Ok(con),
+            Err(e) => tracing::debug!("redis primary unavailable for a read: {}", e),
+        }
+
+        // Every pool is busy, the first one to free a connection serves the read.
+        let waits = saturated
+            .into_iter()
+            .map(|replica| self.replicas[replica].acquire().boxed())
+            .chain(iter::once(self.primary.acquire().boxed()));
+
+        future::select_ok(waits).await.map(|(con, _)| con)
+    }
+
+    /// Returns the replica indexes in the order they should be tried.
+    fn replica_order(&self) -> Vec<usize> {
+        let len = self.replicas.len();
+
+        if len == 0 {
+            return Vec::new();
+        }
+
+        match self.load_balancing {
+            LoadBalancing::RoundRobin => {
+                let start = self.next.fetch_add(1, Ordering::Relaxed) % len;
+                (start..len).chain(0..start).collect()
+            }
+            LoadBalancing::Random => {
+                let start = rand::rng().random_range(0..len);
+                (start..len).chain(0..start).collect()
+            }
+            LoadBalancing::LeastInUse => {
+                let mut order: Vec<usize> = (0..len).collect();
+                order.sort_by_key(|&replica| self.replicas[replica].in_use());
+                order
+            }
+        }
+    }
+
+    pub fn primary(&self) -> &RedisPool<F, C> {
+        &self.primary
+    }
+
+    pub fn replicas(&self) -> &[RedisPool<F, C>] {
+        &self.replicas
+    }
+}
+
+impl<F, C> Clone for ReplicatedRedisPool<F, C>
+where
+    F: ConnectionFactory<C> + Send + Sync + Clone + 'static,
+    C: ConnectionLike + Send + 'static,
+{
+    fn clone(&self) -> Self {
+        ReplicatedRedisPool {
+            primary: self.primary.clone(),
+            replicas: self.replicas.clone(),
+            load_balancing: self.load_balancing,
+            next: self.next.clone(),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use redis::{Arg, Cmd, Pipeline};
+
+/// What happens to a connection returned to the pool after commands sent
+/// through it changed its session state, such as an open `MULTI`, a `WATCH`
+/// or `CLIENT REPLY OFF`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ResetPolicy {
+    /// Undoes the changes in a task spawned on the current runtime before
+    /// queuing the connection again.
+    ///
+    /// Connections which sent `SELECT`, `SUBSCRIBE`, `PSUBSCRIBE`,
+    /// `SSUBSCRIBE`, `MONITOR`, `RESET` or `CLIENT REPLY SKIP` are closed
+    /// instead, as their previous state can not be restored.
+    #[default]
+    Reset,
+    /// Closes every connection which changed its state.
+    Discard,
+    /// Queues connections as they are returned.
+    Keep,
+}
+
+/// Session state changed by the commands sent through a pool connection.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct SessionState {
+    transaction: bool,
+    watching: bool,
+    reply_off: bool,
+    /// A change which can not be undone.
+    tainted: bool,
+}
+
+impl SessionState {
+    /// Records the changes made by `cmd`, whether or not it succeeded.
+    pub(crate) fn observe(&mut self, cmd: &Cmd) {
+        let mut args = cmd.args_iter().filter_map(|arg| match arg {
+            Arg::Simple(arg) => Some(arg),
+            _ => None,
+        });
+        let Some(name) = args.next() else {
+            return;
+        };
+
+        match name.to_ascii_uppercase().as_slice() {
+            b"MULTI" => self.transaction = true,
+            b"EXEC" | b"DISCARD" => {
+                self.transaction = false;
+                self.watching = false;
+            }
+            b"WATCH" => self.watching = true,
+            b"UNWATCH" if !self.transaction => self.watching = false,
+            b"SELECT" | b"SUBSCRIBE" | b"PSUBSCRIBE" | b"SSUBSCRIBE" | b"MONITOR" | b"RESET" => {
+                self.tainted = true
+            }
+            b"CLIENT"
+                if args
+                    .next()
+                    .is_some_and(|sub| sub.eq_ignore_ascii_case(b"REPLY")) =>
+            {
+                match args.next() {
+                    Some(mode) if mode.eq_ignore_ascii_case(b"ON") => self.reply_off = false,
+                    Some(mode) if mode.eq_ignore_ascii_case(b"OFF") => self.reply_off = true,
+                    _ => self.tainted = true,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Records the changes made by every command of `pipe`.
+    pub(crate) fn observe_pipeline(&mut self, pipe: &Pipeline) {
+        pipe.cmd_iter().for_each(|cmd| self.observe(cmd));
+    }
+
+    pub(crate) fn is_dirty(&self) -> bool {
+        self.transaction || self.watching || self.reply_off || self.tainted
+    }
+
+    /// Commands undoing the changes, `None` if they can not be undone.
+    pub(crate) fn reset(&self) -> Option<Pipeline> {
+        // Commands sent within the transaction are only queued, so replies can
+        // not be turned back on before it is discarded.
+        if self.tainted || (self.reply_off && self.transaction) {
+            return None;
+        }
+
+        let mut pipe = redis::pipe();
+
+        if self.reply_off {
+            pipe.cmd("CLIENT").arg("REPLY").arg("ON").ignore();
+        }
+
+        // Discarding the transaction also drops the watched keys.
+        if self.transaction {
+            pipe.cmd("DISCARD").ignore();
+        } else if self.watching {
+            pipe.cmd("UNWATCH").ignore();
+        }
+
+        Some(pipe)
+    }
+}
@@ -0,0 +1,135 @@
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+/// How many times the pool calls the connection factory before giving up on
+/// creating a connection, and how long it waits in between.
+///
+/// The wait doubles after every failed attempt up to `max_backoff`, and a
+/// random jitter of up to half of it is cut off so pools retrying together do
+/// not reconnect in lockstep.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    /// Creates connections in a single attempt.
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            initial_backoff: Duration::from_millis(50),
+            max_backoff: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Tries up to `max_attempts` times, the first attempt included.
+    pub fn new(max_attempts: u32) -> Self {
+        RetryPolicy {
+            max_attempts,
+            ..Default::default()
+        }
+    }
+
+    /// Wait after the first failed attempt, 50 milliseconds by default.
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Longest wait between two attempts, 2 seconds by default.
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Picks the wait after `failures` failed attempts.
+    pub(crate) fn backoff(&self, failures: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+            .min(self.max_backoff);
+        let jitter = rand::rng().random_range(Duration::ZERO..=backoff / 2);
+
+        backoff - jitter
+    }
+}
+
+/// Stops calling the connection factory after `failure_threshold` consecutive
+/// failures, until a single probe is let through `open_timeout` later.
+pub(crate) struct CircuitBreaker {
+    failure_threshold: u32,
+    open_timeout: Duration,
+    state: parking_lot::Mutex<BreakerState>,
+}
+
+enum BreakerState {
+    Closed {
+        failures: u32,
+    },
+    Open {
+        until: Instant,
+    },
+    /// A probe is running, others are rejected until it finishes or is given
+    /// up after another `open_timeout`.
+    HalfOpen {
+        since: Instant,
+    },
+}
+
+impl CircuitBreaker {
+    pub(crate) fn new(failure_threshold: u32, open_timeout: Duration) -> Self {
+        CircuitBreaker {
+            failure_threshold,
+            open_timeout,
+            state: parking_lot::Mutex::new(BreakerState::Closed { failures: 0 }),
+        }
+    }
+
+    /// Whether the factory may be called right now.
+    pub(crate) fn allow(&self) -> bool {
+        let mut state = self.state.lock();
+
+        match *state {
+            BreakerState::Closed { .. } => true,
+            BreakerState::Open { until } if Instant::now() < until => false,
+            BreakerState::HalfOpen { since } if since.elapsed() < self.open_timeout => false,
+            _ => {
+                *state = BreakerState::HalfOpen {
+                    since: Instant::now(),
+                };
+                true
+            }
+        }
+    }
+
+    pub(crate) fn succeeded(&self) {
+        *self.state.lock() = BreakerState::Closed { failures: 0 };
+    }
+
+    pub(crate) fn failed(&self) {
+        let mut state = self.state.lock();
+
+        let failures = match *state {
+            BreakerState::Closed { failures } => failures + 1,
+            _ => self.failure_threshold,
+        };
+
+        *state = if failures >= self.failure_threshold {
+            tracing::warn!("redis circuit breaker opened");
+            BreakerState::Open {
+                until: Instant::now() + self.open_timeout,
+            }
+        } else {
+            BreakerState::Closed { failures }
+        };
+    }
+}
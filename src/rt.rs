@@ -0,0 +1,124 @@
+//! Runtime the pool spawns its background tasks and timers on.
+//!
+//! The `runtime-tokio`, `runtime-async-std` and `runtime-smol` features pick
+//! the runtimes which can be used. When several are enabled, tokio is used from
+//! within a tokio runtime and the others otherwise. The pool only uses
+//! `tokio::sync` primitives besides these, which work on any runtime.
+
+use std::{future::Future, pin::pin, time::Duration};
+
+use futures_util::future::{self, AbortHandle, Either};
+
+#[cfg(not(any(
+    feature = "runtime-tokio",
+    feature = "runtime-async-std",
+    feature = "runtime-smol"
+)))]
+compile_error!("one of the runtime-tokio, runtime-async-std or runtime-smol features is required");
+
+#[derive(Debug, Clone, Copy)]
+enum Runtime {
+    #[cfg(feature = "runtime-tokio")]
+    Tokio,
+    #[cfg(feature = "runtime-async-std")]
+    AsyncStd,
+    #[cfg(feature = "runtime-smol")]
+    Smol,
+}
+
+/// Runtime used outside of a tokio runtime, whose executor does not need to be
+/// entered to spawn tasks.
+#[cfg(feature = "runtime-async-std")]
+const GLOBAL: Option<Runtime> = Some(Runtime::AsyncStd);
+#[cfg(all(feature = "runtime-smol", not(feature = "runtime-async-std")))]
+const GLOBAL: Option<Runtime> = Some(Runtime::Smol);
+#[cfg(not(any(feature = "runtime-async-std", feature = "runtime-smol")))]
+const GLOBAL: Option<Runtime> = None;
+
+fn current() -> Option<Runtime> {
+    #[cfg(feature = "runtime-tokio")]
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return Some(Runtime::Tokio);
+    }
+
+    GLOBAL
+}
+
+/// Whether background tasks can be spawned from here.
+pub(crate) fn can_spawn() -> bool {
+    current().is_some()
+}
+
+/// Background task spawned by the pool, which keeps running once its handle is
+/// dropped.
+#[derive(Debug)]
+pub struct Task {
+    abort: AbortHandle,
+}
+
+impl Task {
+    /// Stops the task the next time it yields.
+    pub fn abort(&self) {
+        self.abort.abort();
+    }
+
+    /// Whether [`abort`](Self::abort) was called.
+    pub fn is_aborted(&self) -> bool {
+        self.abort.is_aborted()
+    }
+}
+
+/// Spawns `fut` on the current runtime.
+///
+/// # Panics
+///
+/// Panics if only `runtime-tokio` is enabled and this is called outside of a
+/// tokio runtime.
+pub(crate) fn spawn<F>(fut: F) -> Task
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let (fut, abort) = future::abortable(fut);
+    let fut = async move {
+        let _ = fut.await;
+    };
+
+    match current() {
+        #[cfg(feature = "runtime-tokio")]
+        Some(Runtime::Tokio) => {
+            tokio::spawn(fut);
+        }
+        #[cfg(feature = "runtime-async-std")]
+        Some(Runtime::AsyncStd) => {
+            async_std::task::spawn(fut);
+        }
+        #[cfg(feature = "runtime-smol")]
+        Some(Runtime::Smol) => smol::spawn(fut).detach(),
+        None => panic!("redis pool tasks must be spawned within a tokio runtime"),
+    }
+
+    Task { abort }
+}
+
+/// Waits for `duration` on the current runtime's timer.
+pub(crate) async fn sleep(duration: Duration) {
+    match current() {
+        #[cfg(feature = "runtime-tokio")]
+        Some(Runtime::Tokio) => tokio::time::sleep(duration).await,
+        #[cfg(feature = "runtime-async-std")]
+        Some(Runtime::AsyncStd) => async_std::task::sleep(duration).await,
+        #[cfg(feature = "runtime-smol")]
+        Some(Runtime::Smol) => {
+            smol::Timer::after(duration).await;
+        }
+        None => panic!("redis pool timers must be used within a tokio runtime"),
+    }
+}
+
+/// Runs `fut` for up to `duration`, returning `None` if it did not finish.
+pub(crate) async fn timeout<F: Future>(duration: Duration, fut: F) -> Option<F::Output> {
+    match future::select(pin!(fut), pin!(sleep(duration))).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
@@ -0,0 +1,312 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Weak,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use redis::{
+    aio::{ConnectionLike, MultiplexedConnection},
+    sentinel::{SentinelClient, SentinelNodeConnectionInfo, SentinelServerType},
+    Client, Cmd, ErrorKind, IntoConnectionInfo, Pipeline, RedisError, RedisFuture, RedisResult,
+    ServerErrorKind, Value,
+};
+
+use crate::{
+    factory::ConnectionFactory,
+    pool::{RedisPool, DEFAULT_CON_LIMIT, DEFAULT_POOL_SIZE},
+    rt::{self, Task},
+};
+
+pub type SentinelRedisPool = RedisPool<SentinelFactory, SentinelConnection>;
+
+impl From<SentinelFactory> for SentinelRedisPool {
+    fn from(value: SentinelFactory) -> Self {
+        RedisPool::new(value, DEFAULT_POOL_SIZE, Some(DEFAULT_CON_LIMIT))
+    }
+}
+
+/// Channel on which the sentinels announce a new master.
+const SWITCH_MASTER_CHANNEL: &str = "+switch-master";
+
+/// Wait before subscribing to the sentinels again after losing the connection.
+const WATCH_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Creates connections to the master of a service monitored by Redis Sentinel.
+///
+/// The master address is discovered through the sentinels and cached until a
+/// failover is detected, either from a `READONLY` error on one of its
+/// connections or from a `+switch-master` notification. Connections to the
+/// previous master then fail with an IO error and are no longer
+/// [reusable](ConnectionFactory::is_reusable), so the pool closes them
+/// whatever its health check and creates new ones to the promoted master.
+#[derive(Clone)]
+pub struct SentinelFactory {
+    inner: Arc<SentinelInner>,
+}
+
+impl SentinelFactory {
+    /// Discovers the master of `service_name` through the given sentinels.
+    pub fn new<T: IntoConnectionInfo>(
+        sentinels: Vec<T>,
+        service_name: impl Into<String>,
+    ) -> RedisResult<Self> {
+        Self::build(sentinels, service_name.into(), None)
+    }
+
+    /// Like [`new`](Self::new), connecting to the master with the credentials,
+    /// database and TLS mode of `node_connection_info`.
+    pub fn with_node_connection_info<T: IntoConnectionInfo>(
+        sentinels: Vec<T>,
+        service_name: impl Into<String>,
+        node_connection_info: SentinelNodeConnectionInfo,
+    ) -> RedisResult<Self> {
+        Self::build(sentinels, service_name.into(), Some(node_connection_info))
+    }
+
+    fn build<T: IntoConnectionInfo>(
+        sentinels: Vec<T>,
+        service_name: String,
+        node_connection_info: Option<SentinelNodeConnectionInfo>,
+    ) -> RedisResult<Self> {
+        let client = SentinelClient::build(
+            sentinels,
+            service_name.clone(),
+            node_connection_info,
+            SentinelServerType::Master,
+        )?;
+
+        Ok(SentinelFactory {
+            inner: Arc::new(SentinelInner {
+                service_name,
+                client: tokio::sync::Mutex::new(client),
+                master: parking_lot::Mutex::new(None),
+                epoch: AtomicU64::new(0),
+                watcher: parking_lot::Mutex::new(None),
+            }),
+        })
+    }
+
+    pub fn service_name(&self) -> &str {
+        &self.inner.service_name
+    }
+
+    /// Forgets the current master, making every existing connection stale.
+    pub fn invalidate(&self) {
+        self.inner.failover(self.inner.epoch());
+    }
+}
+
+#[async_trait]
+impl ConnectionFactory<SentinelConnection> for SentinelFactory {
+    async fn create(&self) -> RedisResult<SentinelConnection> {
+        self.inner.watch();
+
+        let epoch = self.inner.epoch();
+        let con = self
+            .inner
+            .master(epoch)
+            .await?
+            .get_multiplexed_async_connection()
+            .await?;
+
+        Ok(SentinelConnection {
+            con,
+            epoch,
+            inner: self.inner.clone(),
+        })
+    }
+
+    fn is_reusable(&self, con: &SentinelConnection) -> bool {
+        !con.is_stale()
+    }
+}
+
+struct SentinelInner {
+    service_name: String,
+    client: tokio::sync::Mutex<SentinelClient>,
+    master: parking_lot::Mutex<Option<Client>>,
+    /// Bumped on every failover, connections created before are stale.
+    epoch: AtomicU64,
+    watcher: parking_lot::Mutex<Option<Task>>,
+}
+
+impl SentinelInner {
+    fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::SeqCst)
+    }
+
+    /// Returns the cached master client, asking the sentinels for it if needed.
+    async fn master(&self, epoch: u64) -> RedisResult<Client> {
+        if let Some(master) = self.master.lock().clone() {
+            return Ok(master);
+        }
+
+        let master = self.client.lock().await.async_get_client().await?;
+
+        if self.epoch() == epoch {
+            *self.master.lock() = Some(master.clone());
+        }
+
+        Ok(master)
+    }
+
+    /// Records a failover seen by a connection created at `epoch`, only the
+    /// first connection to notice it starts a new epoch.
+    fn failover(&self, epoch: u64) {
+        if self
+            .epoch
+            .compare_exchange(epoch, epoch + 1, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+        {
+            self.master.lock().take();
+            tracing::warn!("redis sentinel master of {} changed", self.service_name);
+        }
+    }
+
+    /// Starts listening for `+switch-master` notifications, once.
+    fn watch(self: &Arc<Self>) {
+        let mut watcher = self.watcher.lock();
+
+        if watcher.is_none() {
+            *watcher = Some(rt::spawn(watch_switch_master(Arc::downgrade(self))));
+        }
+    }
+}
+
+impl Drop for SentinelInner {
+    fn drop(&mut self) {
+        if let Some(watcher) = self.watcher.get_mut().take() {
+            watcher.abort();
+        }
+    }
+}
+
+/// Subscribes to the sentinels and starts a new epoch whenever they announce a
+/// new master for the service.
+async fn watch_switch_master(inner: Weak<SentinelInner>) {
+    loop {
+        let Some(strong) = inner.upgrade() else {
+            return;
+        };
+
+        let subscribed = async {
+            let client = strong
+                .client
+                .lock()
+                .await
+                .async_get_sentinel_client()
+                .await?;
+            let mut pubsub = client.get_async_pubsub().await?;
+
+            pubsub.subscribe(SWITCH_MASTER_CHANNEL).await?;
+            Ok::<_, RedisError>(pubsub.into_on_message())
+        }
+        .await;
+
+        drop(strong);
+
+        match subscribed {
+            Ok(mut messages) => {
+                while let Some(msg) = messages.next().await {
+                    let Some(inner) = inner.upgrade() else {
+                        return;
+                    };
+
+                    // <master name> <old ip> <old port> <new ip> <new port>
+                    let payload = msg.get_payload::<String>().unwrap_or_default();
+
+                    if payload.split(' ').next() == Some(inner.service_name.as_str()) {
+                        inner.failover(inner.epoch());
+                    }
+                }
+
+                tracing::debug!(
+                    "lost the redis sentinel {} subscription",
+                    SWITCH_MASTER_CHANNEL
+                );
+            }
+            Err(e) => {
+                tracing::warn!("failed to subscribe to redis sentinel: {}", e);
+            }
+        }
+
+        rt::sleep(WATCH_RETRY_DELAY).await;
+    }
+}
+
+/// Connection to the master created by a [`SentinelFactory`].
+pub struct SentinelConnection {
+    con: MultiplexedConnection,
+    epoch: u64,
+    inner: Arc<SentinelInner>,
+}
+
+impl SentinelConnection {
+    /// Whether a failover happened since this connection was created.
+    pub fn is_stale(&self) -> bool {
+        self.epoch != self.inner.epoch()
+    }
+
+    fn ensure_current(&self) -> RedisResult<()> {
+        if self.is_stale() {
+            return Err(RedisError::from((
+                ErrorKind::Io,
+                "redis sentinel master changed",
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Starts a new epoch once a `READONLY` reply shows this connection reached
+    /// a replica.
+    fn observe(&self, readonly: bool) {
+        if readonly {
+            self.inner.failover(self.epoch);
+        }
+    }
+}
+
+fn is_readonly(err: &RedisError) -> bool {
+    err.kind() == ErrorKind::Server(ServerErrorKind::ReadOnly)
+}
+
+impl ConnectionLike for SentinelConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        Box::pin(async move {
+            self.ensure_current()?;
+
+            let res = self.con.req_packed_command(cmd).await;
+            self.observe(matches!(&res, Err(e) if is_readonly(e)));
+            res
+        })
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        Box::pin(async move {
+            self.ensure_current()?;
+
+            let res = self.con.req_packed_commands(cmd, offset, count).await;
+            self.observe(match &res {
+                Ok(values) => values.iter().any(|value| {
+                    matches!(value, Value::ServerError(e) if e.kind() == Some(ServerErrorKind::ReadOnly))
+                }),
+                Err(e) => is_readonly(e),
+            });
+            res
+        })
+    }
+
+    fn get_db(&self) -> i64 {
+        self.con.get_db()
+    }
+}
@@ -0,0 +1,221 @@
+use std::{collections::HashSet, sync::Arc};
+
+use redis::aio::ConnectionLike;
+
+use crate::{
+    connection::RedisPoolConnection,
+    errors::{ConfigError, RedisPoolError},
+    factory::ConnectionFactory,
+    pool::RedisPool,
+};
+
+/// Points placed on the hash ring for a shard of weight one.
+pub const DEFAULT_VIRTUAL_NODES: usize = 160;
+
+/// One of the pools of a [`ShardedRedisPool`].
+pub struct Shard<F, C>
+where
+    F: ConnectionFactory<C> + Send + Sync + Clone,
+    C: ConnectionLike + Send,
+{
+    name: String,
+    weight: usize,
+    pool: RedisPool<F, C>,
+}
+
+impl<F, C> Shard<F, C>
+where
+    F: ConnectionFactory<C> + Send + Sync + Clone + 'static,
+    C: ConnectionLike + Send + 'static,
+{
+    /// Name the shard is placed on the ring by.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn weight(&self) -> usize {
+        self.weight
+    }
+
+    pub fn pool(&self) -> &RedisPool<F, C> {
+        &self.pool
+    }
+}
+
+/// Partitions keys between independent pools with a consistent hash ring.
+///
+/// Each shard is placed on the ring by its name, with a number of virtual
+/// nodes proportional to its weight, so adding or removing a shard only moves
+/// the keys of its own ring segments. Like Redis Cluster only the part of a key
+/// between the first `{` and the next `}` is hashed when it is not empty, so
+/// `{user:1}:name` and `{user:1}:email` land on the same shard.
+pub struct ShardedRedisPool<F, C>
+where
+    F: ConnectionFactory<C> + Send + Sync + Clone,
+    C: ConnectionLike + Send,
+{
+    shards: Arc<[Shard<F, C>]>,
+    /// Sorted ring points and the index of the shard owning them.
+    ring: Arc<[(u64, usize)]>,
+}
+
+impl<F, C> ShardedRedisPool<F, C>
+where
+    F: ConnectionFactory<C> + Send + Sync + Clone + 'static,
+    C: ConnectionLike + Send + 'static,
+{
+    pub fn builder() -> ShardedRedisPoolBuilder<F, C> {
+        ShardedRedisPoolBuilder {
+            shards: Vec::new(),
+            virtual_nodes: DEFAULT_VIRTUAL_NODES,
+        }
+    }
+
+    /// Acquires a connection from the shard owning `key`.
+    pub async fn acquire_for_key(
+        &self,
+        key: impl AsRef<[u8]>,
+    ) -> Result<RedisPoolConnection<C>, RedisPoolError> {
+        self.shard_for_key(key).pool.acquire().await
+    }
+
+    /// Returns the shard owning `key`.
+    pub fn shard_for_key(&self, key: impl AsRef<[u8]>) -> &Shard<F, C> {
+        let hash = hash(hash_tag(key.as_ref()));
+        let point = self.ring.partition_point(|&(point, _)| point < hash);
+        let (_, shard) = self.ring[point % self.ring.len()];
+
+        &self.shards[shard]
+    }
+
+    pub fn shards(&self) -> &[Shard<F, C>] {
+        &self.shards
+    }
+}
+
+impl<F, C> Clone for ShardedRedisPool<F, C>
+where
+    F: ConnectionFactory<C> + Send + Sync + Clone + 'static,
+    C: ConnectionLike + Send + 'static,
+{
+    fn clone(&self) -> Self {
+        ShardedRedisPool {
+            shards: self.shards.clone(),
+            ring: self.ring.clone(),
+        }
+    }
+}
+
+/// Builder for a [`ShardedRedisPool`], created with [`ShardedRedisPool::builder`].
+pub struct ShardedRedisPoolBuilder<F, C>
+where
+    F: ConnectionFactory<C> + Send + Sync + Clone,
+    C: ConnectionLike + Send,
+{
+    shards: Vec<Shard<F, C>>,
+    virtual_nodes: usize,
+}
+
+impl<F, C> ShardedRedisPoolBuilder<F, C>
+where
+    F: ConnectionFactory<C> + Send + Sync + Clone + 'static,
+    C: ConnectionLike + Send + 'static,
+{
+    /// Adds a shard of weight one.
+    ///
+    /// Its `name` decides where it is placed on the ring and must stay the
+    /// same across restarts, for example the address of the redis node.
+    pub fn shard(self, name: impl Into<String>, pool: RedisPool<F, C>) -> Self {
+        self.weighted_shard(name, pool, 1)
+    }
+
+    /// Adds a shard owning about `weight` times more keys than a shard of
+    /// weight one.
+    pub fn weighted_shard(
+        mut self,
+        name: impl Into<String>,
+        pool: RedisPool<F, C>,
+        weight: usize,
+    ) -> Self {
+        self.shards.push(Shard {
+            name: name.into(),
+            weight,
+            pool,
+        });
+        self
+    }
+
+    /// Ring points per unit of shard weight, more points spread the keys more
+    /// evenly.
+    ///
+    /// Defaults to [`DEFAULT_VIRTUAL_NODES`].
+    pub fn virtual_nodes(mut self, virtual_nodes: usize) -> Self {
+        self.virtual_nodes = virtual_nodes;
+        self
+    }
+
+    /// Validates the shards and builds the hash ring.
+    pub fn build(self) -> Result<ShardedRedisPool<F, C>, ConfigError> {
+        if self.shards.is_empty() {
+            return Err(ConfigError::NoEndpoints);
+        }
+
+        if self.virtual_nodes == 0 {
+            return Err(ConfigError::ZeroVirtualNodes);
+        }
+
+        let mut names = HashSet::new();
+        let mut ring = Vec::new();
+
+        for (index, shard) in self.shards.iter().enumerate() {
+            if shard.weight == 0 {
+                return Err(ConfigError::ZeroShardWeight);
+            }
+
+            if !names.insert(shard.name.as_str()) {
+                return Err(ConfigError::DuplicateShard(shard.name.clone()));
+            }
+
+            for node in 0..self.virtual_nodes * shard.weight {
+                ring.push((hash(format!("{}-{}", shard.name, node).as_bytes()), index));
+            }
+        }
+
+        ring.sort_unstable();
+
+        Ok(ShardedRedisPool {
+            shards: self.shards.into(),
+            ring: ring.into(),
+        })
+    }
+}
+
+/// Returns the part of `key` hashed to pick its shard.
+fn hash_tag(key: &[u8]) -> &[u8] {
+    if let Some(open) = key.iter().position(|&b| b == b'{') {
+        if let Some(len) = key[open + 1..].iter().position(|&b| b == b'}') {
+            if len > 0 {
+                return &key[open + 1..open + 1 + len];
+            }
+        }
+    }
+
+    key
+}
+
+/// FNV-1a followed by the murmur3 finalizer, stable across builds and
+/// platforms so keys keep their shard after a restart.
+fn hash(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}
@@ -0,0 +1,190 @@
+use std::{
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+#[cfg(feature = "metrics")]
+use crate::recorder::PoolMetrics;
+
+/// Point in time snapshot of a pool, returned by [`RedisPool::status`](crate::RedisPool::status).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStatus {
+    /// Connections waiting in the pool queue.
+    pub idle: usize,
+    /// Connections currently handed out.
+    pub in_use: usize,
+    /// Connection limit of the pool, `None` if unlimited.
+    pub max: Option<usize>,
+    /// Connections created by the factory since the pool was built.
+    pub total_created: u64,
+    /// Queued connections dropped because they failed the health check or
+    /// their factory no longer considered them reusable.
+    pub total_evicted_unhealthy: u64,
+    /// Returned connections dropped because the pool queue was full.
+    pub total_dropped_queue_full: u64,
+    /// Tasks waiting for a connection permit.
+    pub waiters: usize,
+    /// Connections detached with their permit, which still count against the
+    /// connection limit.
+    pub detached: usize,
+}
+
+/// Point in time snapshot of a tenant, returned by
+/// [`RedisPool::tenant_status`](crate::RedisPool::tenant_status).
+///
+/// Tenant counters are not reported to the `metrics` facade, as tenant names
+/// would make for unbounded labels.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TenantStatus {
+    /// Connections currently handed out to the tenant.
+    pub in_use: usize,
+    /// Connection quota of the tenant, `None` if only the pool limit applies.
+    pub max: Option<usize>,
+    /// Tasks of the tenant waiting for it to get below its quota.
+    pub waiters: usize,
+    /// Connections handed out to the tenant since the pool was built. Tenants
+    /// without a [`tenant_limit`](crate::RedisPoolBuilder::tenant_limit) are
+    /// forgotten once they have no connection or waiter, restarting this count.
+    pub total_acquired: u64,
+}
+
+/// Counters shared by a pool and its connections, also reported to the
+/// `metrics` facade when the feature is enabled.
+pub(crate) struct PoolStats {
+    in_use: AtomicUsize,
+    waiters: AtomicUsize,
+    detached: AtomicUsize,
+    total_created: AtomicU64,
+    total_evicted_unhealthy: AtomicU64,
+    total_dropped_queue_full: AtomicU64,
+    #[cfg(feature = "metrics")]
+    metrics: PoolMetrics,
+}
+
+impl PoolStats {
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    pub(crate) fn new(name: &str) -> Self {
+        PoolStats {
+            in_use: AtomicUsize::new(0),
+            waiters: AtomicUsize::new(0),
+            detached: AtomicUsize::new(0),
+            total_created: AtomicU64::new(0),
+            total_evicted_unhealthy: AtomicU64::new(0),
+            total_dropped_queue_full: AtomicU64::new(0),
+            #[cfg(feature = "metrics")]
+            metrics: PoolMetrics::new(name),
+        }
+    }
+
+    pub(crate) fn snapshot(&self, idle: usize, max: Option<usize>) -> PoolStatus {
+        PoolStatus {
+            idle,
+            in_use: self.in_use.load(Ordering::Relaxed),
+            max,
+            total_created: self.total_created.load(Ordering::Relaxed),
+            total_evicted_unhealthy: self.total_evicted_unhealthy.load(Ordering::Relaxed),
+            total_dropped_queue_full: self.total_dropped_queue_full.load(Ordering::Relaxed),
+            waiters: self.waiters.load(Ordering::Relaxed),
+            detached: self.detached.load(Ordering::Relaxed),
+        }
+    }
+
+    pub(crate) fn in_use(&self) -> usize {
+        self.in_use.load(Ordering::SeqCst)
+    }
+
+    /// Records a connection handed out after waiting `waited` for it.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    pub(crate) fn checked_out(&self, waited: Duration) {
+        self.in_use.fetch_add(1, Ordering::SeqCst);
+
+        #[cfg(feature = "metrics")]
+        {
+            self.metrics.in_use.increment(1.0);
+            self.metrics.acquire.record(waited);
+        }
+    }
+
+    /// Records a connection given back or detached after being held for `held`.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    pub(crate) fn checked_in(&self, held: Duration) {
+        self.in_use.fetch_sub(1, Ordering::SeqCst);
+
+        #[cfg(feature = "metrics")]
+        {
+            self.metrics.in_use.decrement(1.0);
+            self.metrics.checkout.record(held);
+        }
+    }
+
+    /// Records a connection the factory created in `took`.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    pub(crate) fn created(&self, took: Duration) {
+        self.total_created.fetch_add(1, Ordering::Relaxed);
+
+        #[cfg(feature = "metrics")]
+        {
+            self.metrics.created.increment(1);
+            self.metrics.create.record(took);
+        }
+    }
+
+    /// Records a connection detached along with its permit.
+    pub(crate) fn detached(&self) {
+        self.detached.fetch_add(1, Ordering::Relaxed);
+
+        #[cfg(feature = "metrics")]
+        self.metrics.detached.increment(1.0);
+    }
+
+    /// Records a detached connection giving its permit back.
+    pub(crate) fn detached_dropped(&self) {
+        self.detached.fetch_sub(1, Ordering::Relaxed);
+
+        #[cfg(feature = "metrics")]
+        self.metrics.detached.decrement(1.0);
+    }
+
+    pub(crate) fn evicted_unhealthy(&self) {
+        self.total_evicted_unhealthy.fetch_add(1, Ordering::Relaxed);
+
+        #[cfg(feature = "metrics")]
+        self.metrics.health_check_failures.increment(1);
+    }
+
+    pub(crate) fn dropped_queue_full(&self) {
+        self.total_dropped_queue_full
+            .fetch_add(1, Ordering::Relaxed);
+
+        #[cfg(feature = "metrics")]
+        self.metrics.queue_full_drops.increment(1);
+    }
+
+    /// Reports the current length of the pool queue.
+    #[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+    pub(crate) fn idle(&self, idle: usize) {
+        #[cfg(feature = "metrics")]
+        self.metrics.idle.set(idle as f64);
+    }
+
+    /// Counts the caller as waiting until the returned guard is dropped.
+    pub(crate) fn waiting(&self) -> WaiterGuard<'_> {
+        self.waiters.fetch_add(1, Ordering::Relaxed);
+
+        #[cfg(feature = "metrics")]
+        self.metrics.waiters.increment(1.0);
+
+        WaiterGuard(self)
+    }
+}
+
+pub(crate) struct WaiterGuard<'a>(&'a PoolStats);
+
+impl Drop for WaiterGuard<'_> {
+    fn drop(&mut self) {
+        self.0.waiters.fetch_sub(1, Ordering::Relaxed);
+
+        #[cfg(feature = "metrics")]
+        self.0.metrics.waiters.decrement(1.0);
+    }
+}
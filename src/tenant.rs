@@ -0,0 +1,188 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use parking_lot::Mutex;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::{builder::PoolConfig, errors::RedisPoolError, stats::TenantStatus};
+
+type Tenants = Arc<Mutex<HashMap<String, Arc<Tenant>>>>;
+
+/// Connection quotas of the tenants sharing a pool, on top of its connection
+/// limit.
+///
+/// Tenants with a configured limit are tracked for the life of the pool. The
+/// others are only tracked while they have connections or waiters, so any
+/// number of tenant names can be used without growing the pool.
+pub(crate) struct TenantQuotas {
+    limits: HashMap<String, usize>,
+    default_limit: Option<usize>,
+    tenants: Tenants,
+}
+
+struct Tenant {
+    max: Option<usize>,
+    sem: Option<Arc<Semaphore>>,
+    /// Kept once idle since it has a configured limit.
+    configured: bool,
+    /// Acquires in progress and permits held, only changed with the tenants
+    /// locked so an idle tenant is forgotten before it can be used again.
+    users: AtomicUsize,
+    in_use: AtomicUsize,
+    waiters: AtomicUsize,
+    total_acquired: AtomicU64,
+}
+
+impl Tenant {
+    fn new(max: Option<usize>, configured: bool) -> Self {
+        Tenant {
+            max,
+            sem: max.map(|max| Arc::new(Semaphore::new(max))),
+            configured,
+            users: AtomicUsize::new(0),
+            in_use: AtomicUsize::new(0),
+            waiters: AtomicUsize::new(0),
+            total_acquired: AtomicU64::new(0),
+        }
+    }
+
+    fn status(&self) -> TenantStatus {
+        TenantStatus {
+            in_use: self.in_use.load(Ordering::Relaxed),
+            max: self.max,
+            waiters: self.waiters.load(Ordering::Relaxed),
+            total_acquired: self.total_acquired.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl TenantQuotas {
+    pub(crate) fn new(config: &PoolConfig) -> Self {
+        TenantQuotas {
+            limits: config.tenant_limits.clone(),
+            default_limit: config.default_tenant_limit,
+            tenants: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn limit(&self, name: &str) -> Option<usize> {
+        self.limits.get(name).copied().or(self.default_limit)
+    }
+
+    fn tenant(&self, name: &str) -> TenantRef {
+        let mut tenants = self.tenants.lock();
+        let tenant = tenants
+            .entry(name.to_owned())
+            .or_insert_with(|| {
+                Arc::new(Tenant::new(
+                    self.limit(name),
+                    self.limits.contains_key(name),
+                ))
+            })
+            .clone();
+        tenant.users.fetch_add(1, Ordering::Relaxed);
+
+        TenantRef {
+            name: name.to_owned(),
+            tenant,
+            tenants: self.tenants.clone(),
+        }
+    }
+
+    /// Waits until `name` is below its quota.
+    pub(crate) async fn acquire(&self, name: &str) -> Result<TenantPermit, RedisPoolError> {
+        let tenant = self.tenant(name);
+
+        let permit = match &tenant.tenant.sem {
+            Some(sem) => {
+                let _waiting = Waiting::new(&tenant.tenant.waiters);
+                let permit = sem
+                    .clone()
+                    .acquire_owned()
+                    .await
+                    .map_err(|_| RedisPoolError::Closed)?;
+                Some(permit)
+            }
+            None => None,
+        };
+
+        tenant.tenant.in_use.fetch_add(1, Ordering::Relaxed);
+        tenant.tenant.total_acquired.fetch_add(1, Ordering::Relaxed);
+
+        Ok(TenantPermit {
+            _permit: permit,
+            tenant,
+        })
+    }
+
+    pub(crate) fn status(&self, name: &str) -> TenantStatus {
+        match self.tenants.lock().get(name) {
+            Some(tenant) => tenant.status(),
+            None => TenantStatus {
+                max: self.limit(name),
+                ..TenantStatus::default()
+            },
+        }
+    }
+
+    /// Fails the tenants' current waiters with [`RedisPoolError::Closed`].
+    pub(crate) fn close(&self) {
+        for tenant in self.tenants.lock().values() {
+            if let Some(sem) = &tenant.sem {
+                sem.close();
+            }
+        }
+    }
+}
+
+/// Counts a waiter until dropped, also when its acquire is cancelled.
+struct Waiting<'a>(&'a AtomicUsize);
+
+impl<'a> Waiting<'a> {
+    fn new(waiters: &'a AtomicUsize) -> Self {
+        waiters.fetch_add(1, Ordering::Relaxed);
+        Waiting(waiters)
+    }
+}
+
+impl Drop for Waiting<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Use of a tenant by an acquire or a permit, forgetting the tenant once its
+/// last use is dropped unless it has a configured limit.
+struct TenantRef {
+    name: String,
+    tenant: Arc<Tenant>,
+    tenants: Tenants,
+}
+
+impl Drop for TenantRef {
+    fn drop(&mut self) {
+        let mut tenants = self.tenants.lock();
+
+        if self.tenant.users.fetch_sub(1, Ordering::Relaxed) == 1 && !self.tenant.configured {
+            tenants.remove(&self.name);
+        }
+    }
+}
+
+/// Share of a tenant's quota held by a connection of
+/// [`acquire_for`](crate::RedisPool::acquire_for).
+pub(crate) struct TenantPermit {
+    _permit: Option<OwnedSemaphorePermit>,
+    tenant: TenantRef,
+}
+
+impl Drop for TenantPermit {
+    fn drop(&mut self) {
+        self.tenant.tenant.in_use.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,159 @@
+mod utils;
+
+use redis::{ConnectionLike, ErrorKind, RedisError, RedisResult, Value};
+use redis_pool::{
+    blocking::{BlockingConnectionFactory, BlockingRedisPool},
+    errors::RedisPoolError,
+    SingleBlockingRedisPool,
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+use testcontainers::clients;
+use utils::TestRedis;
+
+#[derive(Clone, Default)]
+struct SyncMockFactory {
+    created: Arc<AtomicUsize>,
+    failing: Arc<AtomicBool>,
+}
+
+impl SyncMockFactory {
+    fn created(&self) -> usize {
+        self.created.load(Ordering::SeqCst)
+    }
+}
+
+impl BlockingConnectionFactory<SyncMockConnection> for SyncMockFactory {
+    fn create(&self) -> RedisResult<SyncMockConnection> {
+        if self.failing.load(Ordering::SeqCst) {
+            return Err(RedisError::from((ErrorKind::Io, "mock connection refused")));
+        }
+
+        Ok(SyncMockConnection {
+            id: self.created.fetch_add(1, Ordering::SeqCst),
+        })
+    }
+}
+
+struct SyncMockConnection {
+    id: usize,
+}
+
+impl ConnectionLike for SyncMockConnection {
+    fn req_packed_command(&mut self, _cmd: &[u8]) -> RedisResult<Value> {
+        Ok(Value::Okay)
+    }
+
+    fn req_packed_commands(
+        &mut self,
+        _cmd: &[u8],
+        _offset: usize,
+        count: usize,
+    ) -> RedisResult<Vec<Value>> {
+        Ok(vec![Value::Okay; count])
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+
+    fn check_connection(&mut self) -> bool {
+        true
+    }
+
+    fn is_open(&self) -> bool {
+        true
+    }
+}
+
+fn mock_pool(
+    pool_size: usize,
+    con_limit: usize,
+) -> BlockingRedisPool<SyncMockFactory, SyncMockConnection> {
+    BlockingRedisPool::new(SyncMockFactory::default(), pool_size, Some(con_limit))
+}
+
+#[test]
+pub fn test_blocking_get_set() -> anyhow::Result<()> {
+    let docker = clients::Cli::default();
+    let redis = TestRedis::new(&docker);
+    let pool = SingleBlockingRedisPool::from(redis.client());
+
+    for i in 0..50 {
+        let mut con = pool.acquire()?;
+        let (value,) = redis::Pipeline::with_capacity(2)
+            .set("test", i)
+            .ignore()
+            .get("test")
+            .query::<(i64,)>(&mut *con)?;
+        assert_eq!(i, value);
+    }
+
+    Ok(())
+}
+
+#[test]
+pub fn test_blocking_reuses_connections() -> anyhow::Result<()> {
+    let pool = mock_pool(1, 2);
+
+    let id = pool.acquire()?.id;
+    assert_eq!(pool.idle(), 1);
+    assert_eq!(pool.acquire()?.id, id);
+
+    let (first, second) = (pool.acquire()?, pool.acquire()?);
+    assert_eq!(pool.factory().created(), 2);
+
+    drop((first, second));
+    assert_eq!(pool.idle(), 1);
+
+    Ok(())
+}
+
+#[test]
+pub fn test_blocking_limits() -> anyhow::Result<()> {
+    let pool = mock_pool(1, 1);
+    let con = pool.try_acquire()?;
+
+    assert!(matches!(
+        pool.try_acquire(),
+        Err(RedisPoolError::PoolExhausted)
+    ));
+    assert!(matches!(
+        pool.acquire_with_timeout(Duration::from_millis(20)),
+        Err(RedisPoolError::Timeout)
+    ));
+
+    let waiter = {
+        let pool = pool.clone();
+        thread::spawn(move || pool.acquire().map(|con| con.id))
+    };
+
+    thread::sleep(Duration::from_millis(20));
+    drop(con);
+    assert_eq!(waiter.join().unwrap()?, 0);
+
+    let detached = pool.acquire()?.detach();
+    assert_eq!(pool.idle(), 0);
+    assert_ne!(pool.try_acquire()?.id, detached.id);
+
+    Ok(())
+}
+
+#[test]
+pub fn test_blocking_create_error_releases_permit() -> anyhow::Result<()> {
+    let pool = mock_pool(1, 1);
+
+    pool.factory().failing.store(true, Ordering::SeqCst);
+    assert!(matches!(pool.acquire(), Err(RedisPoolError::Redis(_))));
+
+    pool.factory().failing.store(false, Ordering::SeqCst);
+    pool.try_acquire()?;
+
+    Ok(())
+}
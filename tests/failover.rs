@@ -0,0 +1,106 @@
+mod utils;
+
+use redis::Client;
+use redis_pool::{
+    errors::{ConfigError, RedisPoolError},
+    factory::ConnectionFactory,
+    failover::{FailoverFactory, FailoverRedisPool},
+    RedisPool,
+};
+use std::time::Duration;
+use testcontainers::clients::Cli;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    task::{JoinHandle, JoinSet},
+};
+use utils::TestRedis;
+
+fn unreachable_client() -> Client {
+    Client::open("redis://127.0.0.1:1/").unwrap()
+}
+
+#[test]
+pub fn test_no_endpoints() {
+    assert_eq!(
+        FailoverFactory::new(Vec::new()).err(),
+        Some(ConfigError::NoEndpoints)
+    );
+}
+
+#[tokio::test]
+pub async fn test_all_endpoints_down() {
+    let factory = FailoverFactory::new(vec![unreachable_client(), unreachable_client()]).unwrap();
+    let pool = FailoverRedisPool::from(factory.clone());
+
+    assert!(matches!(
+        pool.acquire().await,
+        Err(RedisPoolError::Redis(_))
+    ));
+    assert_eq!(factory.current(), 0);
+}
+
+/// Forwards a local port to redis, so the endpoint can go down and come back.
+struct Proxy {
+    port: u16,
+    task: JoinHandle<()>,
+}
+
+impl Proxy {
+    async fn start(port: u16, target: u16) -> Self {
+        let listener = TcpListener::bind(("127.0.0.1", port)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let task = tokio::spawn(async move {
+            let mut connections = JoinSet::new();
+
+            while let Ok((mut inbound, _)) = listener.accept().await {
+                connections.spawn(async move {
+                    let mut outbound = TcpStream::connect(("127.0.0.1", target)).await?;
+                    tokio::io::copy_bidirectional(&mut inbound, &mut outbound).await
+                });
+            }
+        });
+
+        Proxy { port, task }
+    }
+
+    fn client(&self) -> Client {
+        Client::open(format!("redis://127.0.0.1:{}/", self.port)).unwrap()
+    }
+
+    /// Closes the listener and every forwarded connection.
+    async fn stop(self) -> u16 {
+        self.task.abort();
+        let _ = self.task.await;
+        self.port
+    }
+}
+
+#[tokio::test]
+pub async fn test_failover_and_failback() -> anyhow::Result<()> {
+    let docker = Cli::docker();
+    let redis = TestRedis::new(&docker);
+    let proxy = Proxy::start(0, redis.port()).await;
+    let factory = FailoverFactory::new(vec![proxy.client(), redis.client()])?
+        .failback_interval(Duration::ZERO);
+    let pool = RedisPool::from(factory.clone());
+
+    drop(pool.acquire().await?);
+    assert_eq!(factory.current(), 0);
+
+    // The preferred endpoint goes down, new connections use the next one.
+    let port = proxy.stop().await;
+    let mut con = factory.create().await?;
+    let _: () = redis::cmd("SET")
+        .arg("test")
+        .arg(1)
+        .query_async(&mut con)
+        .await?;
+    assert_eq!(factory.current(), 1);
+
+    // It is probed again on the next creation once it is back up.
+    let _proxy = Proxy::start(port, redis.port()).await;
+    drop(factory.create().await?);
+    assert_eq!(factory.current(), 0);
+
+    Ok(())
+}
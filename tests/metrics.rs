@@ -0,0 +1,93 @@
+#![cfg(feature = "metrics")]
+
+mod utils;
+
+use metrics_util::{
+    debugging::{DebugValue, DebuggingRecorder, Snapshotter},
+    CompositeKey, MetricKind,
+};
+use redis_pool::RedisPool;
+use utils::MockConnectionFactory;
+
+struct Snapshot(Vec<(CompositeKey, DebugValue)>);
+
+impl Snapshot {
+    // Taking a snapshot resets the recorded values.
+    fn take(snapshotter: &Snapshotter) -> Self {
+        Snapshot(
+            snapshotter
+                .snapshot()
+                .into_vec()
+                .into_iter()
+                .map(|(key, _, _, value)| (key, value))
+                .collect(),
+        )
+    }
+
+    fn get(&self, kind: MetricKind, name: &str) -> Option<&DebugValue> {
+        self.0
+            .iter()
+            .find(|(key, _)| {
+                key.kind() == kind
+                    && key.key().name() == name
+                    && key
+                        .key()
+                        .labels()
+                        .any(|label| label.key() == "pool" && label.value() == "cache")
+            })
+            .map(|(_, value)| value)
+    }
+}
+
+#[tokio::test]
+pub async fn test_pool_metrics() -> anyhow::Result<()> {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    let pool = metrics::with_local_recorder(&recorder, || {
+        RedisPool::builder(MockConnectionFactory::default())
+            .name("cache")
+            .build()
+    })?;
+
+    let con = pool.acquire().await?;
+    let snapshot = Snapshot::take(&snapshotter);
+
+    assert!(matches!(
+        snapshot.get(MetricKind::Gauge, "redis_pool_in_use_connections"),
+        Some(DebugValue::Gauge(value)) if *value == 1.0
+    ));
+    assert!(matches!(
+        snapshot.get(MetricKind::Counter, "redis_pool_connections_created_total"),
+        Some(DebugValue::Counter(1))
+    ));
+    assert!(matches!(
+        snapshot.get(MetricKind::Histogram, "redis_pool_create_duration_seconds"),
+        Some(DebugValue::Histogram(values)) if values.len() == 1
+    ));
+    assert!(matches!(
+        snapshot.get(MetricKind::Histogram, "redis_pool_acquire_duration_seconds"),
+        Some(DebugValue::Histogram(values)) if values.len() == 1
+    ));
+
+    drop(con);
+    let snapshot = Snapshot::take(&snapshotter);
+
+    assert!(matches!(
+        snapshot.get(MetricKind::Gauge, "redis_pool_idle_connections"),
+        Some(DebugValue::Gauge(value)) if *value == 1.0
+    ));
+    assert!(matches!(
+        snapshot.get(MetricKind::Histogram, "redis_pool_checkout_duration_seconds"),
+        Some(DebugValue::Histogram(values)) if values.len() == 1
+    ));
+
+    let _detached = pool.acquire().await?.detach_with_permit();
+    let snapshot = Snapshot::take(&snapshotter);
+
+    assert!(matches!(
+        snapshot.get(MetricKind::Gauge, "redis_pool_detached_connections"),
+        Some(DebugValue::Gauge(value)) if *value == 1.0
+    ));
+
+    Ok(())
+}
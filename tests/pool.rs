@@ -3,11 +3,28 @@ mod utils;
 use anyhow::Context;
 use futures::future::join_all;
 use redis::aio::ConnectionLike;
-use redis_pool::{pool::RedisPool, SingleRedisPool};
+use redis::{ErrorKind, RedisError};
+use redis_pool::{
+    errors::{ConfigError, RedisPoolError},
+    health::{CommandCheck, IdleCheck, NoCheck, PingCheck},
+    permit::Priority,
+    pool::RedisPool,
+    reset::ResetPolicy,
+    retry::RetryPolicy,
+    stats::TenantStatus,
+    SingleRedisPool,
+};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 use testcontainers::clients::{self, Cli};
 use utils::TestRedis;
 
-use crate::utils::ClosableConnectionFactory;
+use crate::utils::{ClosableConnectionFactory, MockConnection, MockConnectionFactory};
 
 #[tokio::test]
 pub async fn test_simple_get_set_series() -> anyhow::Result<()> {
@@ -104,3 +121,905 @@ pub async fn test_bad_connection_eviction() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+pub fn test_builder_validation() {
+    let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+
+    let pool = RedisPool::builder(client.clone())
+        .pool_size(4)
+        .con_limit(Some(8))
+        .build()
+        .unwrap();
+    assert_eq!(pool.pool_size(), 4);
+    assert_eq!(pool.con_limit(), Some(8));
+
+    assert_eq!(
+        RedisPool::builder(client.clone())
+            .pool_size(0)
+            .build()
+            .err(),
+        Some(ConfigError::ZeroPoolSize)
+    );
+    assert_eq!(
+        RedisPool::builder(client.clone())
+            .con_limit(Some(0))
+            .build()
+            .err(),
+        Some(ConfigError::ZeroConLimit)
+    );
+    assert_eq!(
+        RedisPool::builder(client.clone())
+            .acquire_timeout(Duration::ZERO)
+            .build()
+            .err(),
+        Some(ConfigError::ZeroAcquireTimeout)
+    );
+    assert_eq!(
+        RedisPool::builder(client.clone())
+            .max_lifetime_jitter(Duration::from_secs(1))
+            .build()
+            .err(),
+        Some(ConfigError::JitterWithoutMaxLifetime)
+    );
+    assert_eq!(
+        RedisPool::builder(client)
+            .pool_size(16)
+            .con_limit(Some(8))
+            .build()
+            .err(),
+        Some(ConfigError::PoolSizeExceedsConLimit {
+            pool_size: 16,
+            con_limit: 8
+        })
+    );
+}
+
+#[tokio::test]
+pub async fn test_try_acquire_and_timeout() -> anyhow::Result<()> {
+    let pool = RedisPool::builder(MockConnectionFactory::default())
+        .pool_size(1)
+        .con_limit(Some(1))
+        .build()?;
+
+    let con = pool.try_acquire().await?;
+
+    assert!(matches!(
+        pool.try_acquire().await,
+        Err(RedisPoolError::PoolExhausted)
+    ));
+    assert!(matches!(
+        pool.acquire_with_timeout(Duration::from_millis(20)).await,
+        Err(RedisPoolError::Timeout)
+    ));
+
+    drop(con);
+    pool.try_acquire().await?;
+
+    let slow = RedisPool::builder(MockConnectionFactory::with_delay(Duration::from_secs(5)))
+        .acquire_timeout(Duration::from_millis(20))
+        .build()?;
+
+    assert!(matches!(slow.acquire().await, Err(RedisPoolError::Timeout)));
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_idle_timeout() -> anyhow::Result<()> {
+    let factory = MockConnectionFactory::default();
+    let pool = RedisPool::builder(factory.clone())
+        .idle_timeout(Duration::from_millis(50))
+        .build()?;
+
+    drop(pool.acquire().await?);
+    assert_eq!(pool.acquire().await?.id, 0);
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    assert_eq!(pool.acquire().await?.id, 1);
+    assert_eq!(factory.created(), 2);
+
+    Ok(())
+}
+
+#[test]
+pub fn test_idle_timeout_requires_runtime() {
+    let client = redis::Client::open("redis://127.0.0.1/").unwrap();
+
+    let pool = RedisPool::builder(client)
+        .idle_timeout(Duration::from_secs(1))
+        .build();
+
+    // The async-std and smol executors run without being entered.
+    if cfg!(any(feature = "runtime-async-std", feature = "runtime-smol")) {
+        assert!(pool.is_ok());
+    } else {
+        assert_eq!(pool.err(), Some(ConfigError::NoRuntime));
+    }
+}
+
+#[tokio::test]
+pub async fn test_max_lifetime() -> anyhow::Result<()> {
+    let factory = MockConnectionFactory::default();
+    let pool = RedisPool::builder(factory.clone())
+        .max_lifetime(Duration::from_millis(100))
+        .max_lifetime_jitter(Duration::from_millis(10))
+        .build()?;
+
+    let con = pool.acquire().await?;
+    assert_eq!(con.id, 0);
+    drop(con);
+    assert_eq!(pool.acquire().await?.id, 0);
+
+    let con = pool.acquire().await?;
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    drop(con);
+
+    assert_eq!(pool.acquire().await?.id, 1);
+    assert_eq!(factory.created(), 2);
+
+    assert_eq!(
+        RedisPool::builder(factory)
+            .max_lifetime(Duration::from_secs(1))
+            .max_lifetime_jitter(Duration::from_secs(1))
+            .build()
+            .err(),
+        Some(ConfigError::JitterExceedsMaxLifetime)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_health_checks() -> anyhow::Result<()> {
+    async fn reused_after_close(pool: RedisPool<MockConnectionFactory, MockConnection>) -> bool {
+        let mut con = pool.acquire().await.unwrap();
+        con.open = false;
+        drop(con);
+
+        pool.acquire().await.unwrap().id == 0
+    }
+
+    let builder = || RedisPool::builder(MockConnectionFactory::default());
+
+    assert!(!reused_after_close(builder().build()?).await);
+    assert!(!reused_after_close(builder().health_check(PingCheck).build()?).await);
+    assert!(
+        !reused_after_close(
+            builder()
+                .health_check(CommandCheck::new(redis::cmd("PING")))
+                .build()?
+        )
+        .await
+    );
+    assert!(reused_after_close(builder().health_check(NoCheck).build()?).await);
+    assert!(
+        reused_after_close(
+            builder()
+                .health_check(IdleCheck::new(Duration::from_secs(60), PingCheck))
+                .build()?
+        )
+        .await
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_min_idle() -> anyhow::Result<()> {
+    let factory = MockConnectionFactory::default();
+    let pool = RedisPool::builder(factory.clone()).min_idle(2).build()?;
+
+    assert_eq!(pool.warm_up().await?, 2);
+    assert_eq!(pool.warm_up().await?, 0);
+
+    let mut first = pool.acquire().await?;
+    let mut second = pool.acquire().await?;
+    assert_eq!(factory.created(), 2);
+
+    first.open = false;
+    second.open = false;
+    drop(first);
+    drop(second);
+
+    // Both queued connections fail the health check, so a third one is created
+    // and the background task refills the two evicted idle connections.
+    let con = pool.acquire().await?;
+    assert_eq!(con.id, 2);
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(factory.created(), 5);
+
+    // Connections in use leave no room under the limit to warm up.
+    let factory = MockConnectionFactory::default();
+    let limited = RedisPool::builder(factory.clone())
+        .pool_size(2)
+        .con_limit(Some(2))
+        .min_idle(2)
+        .build()?;
+    let held = (limited.acquire().await?, limited.acquire().await?);
+    assert_eq!(limited.warm_up().await?, 0);
+    drop(held.0);
+    assert_eq!(limited.warm_up().await?, 0);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(factory.created(), 2);
+    drop(held.1);
+
+    assert_eq!(
+        RedisPool::builder(MockConnectionFactory::default())
+            .pool_size(2)
+            .con_limit(Some(4))
+            .min_idle(3)
+            .build()
+            .err(),
+        Some(ConfigError::MinIdleExceedsPoolSize {
+            min_idle: 3,
+            pool_size: 2
+        })
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_status() -> anyhow::Result<()> {
+    let pool = RedisPool::builder(MockConnectionFactory::default())
+        .pool_size(1)
+        .con_limit(Some(2))
+        .build()?;
+
+    let mut first = pool.acquire().await?;
+    let second = pool.acquire().await?;
+
+    let waiter = tokio::spawn({
+        let pool = pool.clone();
+        async move { pool.acquire().await.map(|con| con.id) }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let status = pool.status();
+    assert_eq!(status.idle, 0);
+    assert_eq!(status.in_use, 2);
+    assert_eq!(status.max, Some(2));
+    assert_eq!(status.total_created, 2);
+    assert_eq!(status.waiters, 1);
+
+    first.open = false;
+    drop(first);
+    drop(second);
+
+    // The closed connection fills the queue so the second one is dropped, then
+    // the waiter evicts the closed one and creates a new connection.
+    assert_eq!(waiter.await??, 2);
+
+    let status = pool.status();
+    assert_eq!(status.idle, 1);
+    assert_eq!(status.total_created, 3);
+    assert_eq!(status.in_use, 0);
+    assert_eq!(status.waiters, 0);
+    assert_eq!(status.total_evicted_unhealthy, 1);
+    assert_eq!(status.total_dropped_queue_full, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_close() -> anyhow::Result<()> {
+    let pool = RedisPool::builder(MockConnectionFactory::default()).build()?;
+
+    drop(pool.acquire().await?);
+    let con = pool.acquire().await?;
+    drop(pool.acquire().await?);
+    assert_eq!(pool.status().idle, 1);
+
+    assert!(matches!(
+        pool.close(Duration::from_millis(20)).await,
+        Err(RedisPoolError::Timeout)
+    ));
+    assert!(pool.is_closed());
+    assert_eq!(pool.status().idle, 0);
+    assert!(matches!(pool.acquire().await, Err(RedisPoolError::Closed)));
+    assert!(matches!(
+        pool.try_acquire().await,
+        Err(RedisPoolError::Closed)
+    ));
+
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        drop(con);
+    });
+
+    pool.close(Duration::from_secs(5)).await?;
+
+    let status = pool.status();
+    assert_eq!(status.idle, 0);
+    assert_eq!(status.in_use, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_hooks() -> anyhow::Result<()> {
+    let connected = Arc::new(AtomicUsize::new(0));
+    let released = Arc::new(AtomicUsize::new(0));
+
+    let pool = RedisPool::builder(MockConnectionFactory::default())
+        .after_connect({
+            let connected = connected.clone();
+            move |con| {
+                connected.fetch_add(1, Ordering::SeqCst);
+                let id = con.id;
+                Box::pin(async move {
+                    match id {
+                        1 => Err(RedisError::from((ErrorKind::Client, "rejected"))),
+                        _ => Ok(()),
+                    }
+                })
+            }
+        })
+        .before_acquire(|con| {
+            let id = con.id;
+            Box::pin(async move {
+                match id {
+                    0 => Err(RedisError::from((ErrorKind::Client, "rejected"))),
+                    _ => Ok(()),
+                }
+            })
+        })
+        .after_release({
+            let released = released.clone();
+            move |_con| {
+                released.fetch_add(1, Ordering::SeqCst);
+                Box::pin(async { Ok(()) })
+            }
+        })
+        .build()?;
+
+    let first = pool.acquire().await?;
+    assert_eq!(first.id, 0);
+    assert!(matches!(
+        pool.acquire().await,
+        Err(RedisPoolError::Redis(_))
+    ));
+    let second = pool.acquire().await?;
+    assert_eq!(second.id, 2);
+    assert_eq!(connected.load(Ordering::SeqCst), 3);
+
+    drop(first);
+    drop(second);
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(released.load(Ordering::SeqCst), 2);
+    assert_eq!(pool.status().idle, 2);
+    assert_eq!(pool.status().in_use, 0);
+
+    // The first connection is rejected before being handed out again.
+    assert_eq!(pool.acquire().await?.id, 2);
+    assert_eq!(connected.load(Ordering::SeqCst), 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_retry() -> anyhow::Result<()> {
+    let factory = MockConnectionFactory::default();
+    let pool = RedisPool::builder(factory.clone())
+        .retry(
+            RetryPolicy::new(3)
+                .initial_backoff(Duration::from_millis(1))
+                .max_backoff(Duration::from_millis(2)),
+        )
+        .build()?;
+
+    // Two failures are retried.
+    factory.fail_next(2);
+    drop(pool.acquire().await?);
+    assert_eq!(factory.attempts(), 3);
+
+    // The third one is returned.
+    factory.set_failing(true);
+    assert!(matches!(
+        pool.try_acquire().await,
+        Err(RedisPoolError::Redis(_))
+    ));
+    assert_eq!(factory.attempts(), 6);
+
+    assert_eq!(
+        RedisPool::builder(factory)
+            .retry(RetryPolicy::new(0))
+            .build()
+            .err(),
+        Some(ConfigError::ZeroMaxAttempts)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_circuit_breaker() -> anyhow::Result<()> {
+    let factory = MockConnectionFactory::default();
+    let pool = RedisPool::builder(factory.clone())
+        .health_check(NoCheck)
+        .circuit_breaker(2, Duration::from_millis(50))
+        .build()?;
+
+    factory.set_failing(true);
+
+    for _ in 0..2 {
+        assert!(matches!(
+            pool.acquire().await,
+            Err(RedisPoolError::Redis(_))
+        ));
+    }
+
+    // Open, the factory is not called anymore.
+    assert!(matches!(
+        pool.acquire().await,
+        Err(RedisPoolError::CircuitOpen)
+    ));
+    assert_eq!(factory.attempts(), 2);
+
+    // Half open, a failed probe opens it again.
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert!(matches!(
+        pool.acquire().await,
+        Err(RedisPoolError::Redis(_))
+    ));
+    assert!(matches!(
+        pool.acquire().await,
+        Err(RedisPoolError::CircuitOpen)
+    ));
+    assert_eq!(factory.attempts(), 3);
+
+    // A successful probe closes it.
+    factory.set_failing(false);
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    let con = pool.acquire().await?;
+    let _other = pool.acquire().await?;
+    drop(con);
+    assert_eq!(factory.attempts(), 5);
+
+    Ok(())
+}
+
+type MockPool = RedisPool<MockConnectionFactory, MockConnection>;
+
+/// Queues one waiter per priority in turn behind `con`, then returns the order
+/// in which they got a connection once it is released.
+async fn serve_waiters(
+    pool: &MockPool,
+    con: redis_pool::connection::RedisPoolConnection<MockConnection>,
+    priorities: &[Priority],
+) -> anyhow::Result<Vec<Priority>> {
+    let served = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut waiters = Vec::new();
+
+    for &priority in priorities {
+        let pool = pool.clone();
+        let served = served.clone();
+
+        waiters.push(tokio::spawn(async move {
+            let con = pool.acquire_with_priority(priority).await?;
+            served.lock().unwrap().push(priority);
+            drop(con);
+            Ok::<_, RedisPoolError>(())
+        }));
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    for priority in [Priority::Low, Priority::Normal, Priority::High] {
+        assert_eq!(
+            pool.waiters(priority),
+            priorities.iter().filter(|&&p| p == priority).count()
+        );
+    }
+
+    drop(con);
+
+    for waiter in join_all(waiters).await {
+        waiter??;
+    }
+
+    let served = served.lock().unwrap().clone();
+    Ok(served)
+}
+
+#[tokio::test]
+pub async fn test_priority() -> anyhow::Result<()> {
+    let pool = RedisPool::builder(MockConnectionFactory::default())
+        .pool_size(1)
+        .con_limit(Some(1))
+        .priority_aging(Duration::from_secs(60))
+        .build()?;
+
+    let con = pool.acquire().await?;
+    assert_eq!(
+        serve_waiters(
+            &pool,
+            con,
+            &[
+                Priority::Low,
+                Priority::Normal,
+                Priority::High,
+                Priority::Low
+            ]
+        )
+        .await?,
+        [
+            Priority::High,
+            Priority::Normal,
+            Priority::Low,
+            Priority::Low
+        ]
+    );
+
+    // Waiters which gave up are skipped and no longer counted.
+    let con = pool.acquire().await?;
+    assert!(matches!(
+        pool.acquire_with_timeout(Duration::from_millis(20)).await,
+        Err(RedisPoolError::Timeout)
+    ));
+    assert_eq!(pool.waiters(Priority::Normal), 0);
+    assert!(matches!(
+        pool.try_acquire().await,
+        Err(RedisPoolError::PoolExhausted)
+    ));
+    drop(con);
+    pool.try_acquire().await?;
+
+    // Closing fails the queued waiters.
+    let con = pool.acquire().await?;
+    let waiter = {
+        let pool = pool.clone();
+        tokio::spawn(async move { pool.acquire_with_priority(Priority::High).await.map(drop) })
+    };
+
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert_eq!(pool.waiters(Priority::High), 1);
+    assert!(matches!(
+        pool.close(Duration::from_millis(20)).await,
+        Err(RedisPoolError::Timeout)
+    ));
+    assert!(matches!(waiter.await?, Err(RedisPoolError::Closed)));
+    assert_eq!(pool.waiters(Priority::High), 0);
+    drop(con);
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_priority_aging() -> anyhow::Result<()> {
+    let pool = RedisPool::builder(MockConnectionFactory::default())
+        .pool_size(1)
+        .con_limit(Some(1))
+        .priority_aging(Duration::from_millis(15))
+        .build()?;
+
+    // The low priority waiter aged past the high priority one by the time the
+    // connection is released.
+    let con = pool.acquire().await?;
+    assert_eq!(
+        serve_waiters(&pool, con, &[Priority::Low, Priority::High]).await?,
+        [Priority::Low, Priority::High]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_tenant_quotas() -> anyhow::Result<()> {
+    let pool = RedisPool::builder(MockConnectionFactory::default())
+        .pool_size(4)
+        .con_limit(Some(4))
+        .tenant_limit("noisy", 2)
+        .default_tenant_limit(3)
+        .build()?;
+
+    let noisy = (
+        pool.acquire_for("noisy").await?,
+        pool.acquire_for("noisy").await?,
+    );
+    assert!(
+        tokio::time::timeout(Duration::from_millis(20), pool.acquire_for("noisy"))
+            .await
+            .is_err()
+    );
+    assert_eq!(
+        pool.tenant_status("noisy"),
+        TenantStatus {
+            in_use: 2,
+            max: Some(2),
+            waiters: 0,
+            total_acquired: 2,
+        }
+    );
+
+    // Other tenants draw from the rest of the pool, reusing the idle
+    // connections returned by the noisy one.
+    let id = noisy.0.id;
+    drop(noisy);
+    let quiet = pool.acquire_for("quiet").await?;
+    assert_eq!(quiet.id, id);
+    assert_eq!(pool.tenant_status("quiet").max, Some(3));
+    assert_eq!(pool.tenant_status("quiet").in_use, 1);
+    assert_eq!(pool.tenant_status("noisy").in_use, 0);
+    assert_eq!(
+        pool.tenant_status("unseen"),
+        TenantStatus {
+            max: Some(3),
+            ..TenantStatus::default()
+        }
+    );
+
+    // Tenants also wait on the pool's connection limit.
+    let _rest = (
+        pool.acquire().await?,
+        pool.acquire().await?,
+        pool.acquire().await?,
+    );
+    assert!(
+        tokio::time::timeout(Duration::from_millis(20), pool.acquire_for("quiet"))
+            .await
+            .is_err()
+    );
+
+    // Only the tenants with their own limit are kept once idle.
+    drop((quiet, _rest));
+    assert_eq!(pool.tenant_status("noisy").total_acquired, 2);
+    assert_eq!(
+        pool.tenant_status("quiet"),
+        TenantStatus {
+            max: Some(3),
+            ..TenantStatus::default()
+        }
+    );
+
+    assert_eq!(
+        RedisPool::builder(MockConnectionFactory::default())
+            .tenant_limit("noisy", 0)
+            .build()
+            .err(),
+        Some(ConfigError::ZeroTenantLimit("noisy".to_owned()))
+    );
+    assert_eq!(
+        RedisPool::builder(MockConnectionFactory::default())
+            .default_tenant_limit(0)
+            .build()
+            .err(),
+        Some(ConfigError::ZeroDefaultTenantLimit)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_resize() -> anyhow::Result<()> {
+    let pool = RedisPool::builder(MockConnectionFactory::default())
+        .pool_size(2)
+        .con_limit(Some(2))
+        .build()?;
+
+    // Growing serves the waiters right away.
+    let held = (pool.acquire().await?, pool.acquire().await?);
+    let waiter = {
+        let pool = pool.clone();
+        tokio::spawn(async move { pool.acquire_with_timeout(Duration::from_secs(1)).await })
+    };
+
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    pool.resize(3, Some(3))?;
+    let third = waiter.await??;
+    assert_eq!((pool.pool_size(), pool.con_limit()), (3, Some(3)));
+    assert_eq!(pool.status().in_use, 3);
+
+    // Shrinking below the connections in use takes effect as they come back,
+    // and the idle connections which do not fit are closed.
+    pool.resize(1, Some(1))?;
+    drop(held);
+    assert!(matches!(
+        pool.try_acquire().await,
+        Err(RedisPoolError::PoolExhausted)
+    ));
+    assert_eq!(pool.status().idle, 1);
+
+    drop(third);
+    assert_eq!(pool.status().idle, 1);
+    let con = pool.try_acquire().await?;
+
+    // Growing again first cancels the permits still to forget.
+    pool.resize(2, Some(2))?;
+    let other = pool.try_acquire().await?;
+    pool.resize(1, Some(1))?;
+    pool.resize(2, Some(2))?;
+    drop((con, other));
+    let _both = (pool.try_acquire().await?, pool.try_acquire().await?);
+    assert!(matches!(
+        pool.try_acquire().await,
+        Err(RedisPoolError::PoolExhausted)
+    ));
+
+    assert_eq!(pool.resize(0, Some(2)), Err(ConfigError::ZeroPoolSize));
+    assert_eq!(pool.resize(2, None), Err(ConfigError::ToggledConLimit));
+    assert_eq!(
+        pool.resize(4, Some(2)),
+        Err(ConfigError::PoolSizeExceedsConLimit {
+            pool_size: 4,
+            con_limit: 2
+        })
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_detach_with_permit() -> anyhow::Result<()> {
+    let pool = RedisPool::builder(MockConnectionFactory::default())
+        .pool_size(2)
+        .con_limit(Some(2))
+        .tenant_limit("tenant", 1)
+        .build()?;
+
+    let mut detached = pool.acquire_for("tenant").await?.detach_with_permit();
+    let pong: String = redis::cmd("PING").query_async(&mut detached).await?;
+    assert_eq!(pong, "PONG");
+
+    let status = pool.status();
+    assert_eq!((status.in_use, status.detached), (0, 1));
+    assert_eq!(pool.tenant_status("tenant").in_use, 1);
+
+    // The detached connection still counts against the connection limit.
+    let con = pool.acquire().await?;
+    assert!(matches!(
+        pool.try_acquire().await,
+        Err(RedisPoolError::PoolExhausted)
+    ));
+
+    drop(detached);
+    assert_eq!(pool.status().detached, 0);
+    assert_eq!(pool.tenant_status("tenant").in_use, 0);
+
+    // Unwrapping it or detaching without the permit frees the permit.
+    let raw = pool.try_acquire().await?.detach_with_permit().into_inner();
+    assert_eq!(pool.status().detached, 0);
+    let plain = con.detach();
+    let _both = (pool.try_acquire().await?, pool.try_acquire().await?);
+    drop((raw, plain));
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_reset_policy() -> anyhow::Result<()> {
+    async fn send(con: &mut impl ConnectionLike, args: &[&str]) -> anyhow::Result<()> {
+        let mut cmd = redis::cmd(args[0]);
+        args[1..].iter().for_each(|arg| {
+            cmd.arg(*arg);
+        });
+        cmd.query_async::<()>(con).await?;
+        Ok(())
+    }
+
+    let factory = MockConnectionFactory::default();
+    let pool = RedisPool::builder(factory.clone())
+        .pool_size(1)
+        .health_check(NoCheck)
+        .build()?;
+
+    // Unfinished transactions, watches and disabled replies are undone
+    // before the connection is queued again.
+    let mut con = pool.acquire().await?;
+    send(&mut con, &["CLIENT", "REPLY", "OFF"]).await?;
+    send(&mut con, &["WATCH", "key"]).await?;
+    drop(con);
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let mut con = pool.acquire().await?;
+    assert_eq!(con.id, 0);
+    assert_eq!(con.sent[2..], ["CLIENT REPLY ON", "UNWATCH"]);
+    send(&mut con, &["WATCH", "key"]).await?;
+    send(&mut con, &["MULTI"]).await?;
+    send(&mut con, &["SET", "key", "value"]).await?;
+    drop(con);
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let mut con = pool.acquire().await?;
+    assert_eq!(con.sent.last().map(String::as_str), Some("DISCARD"));
+
+    // Finished transactions leave nothing to reset.
+    send(&mut con, &["MULTI"]).await?;
+    send(&mut con, &["EXEC"]).await?;
+    let sent = con.sent.len();
+    drop(con);
+
+    let mut con = pool.acquire().await?;
+    assert_eq!(con.sent.len(), sent);
+
+    // A selected database can not be restored, so the connection is closed.
+    send(&mut con, &["select", "5"]).await?;
+    drop(con);
+    assert_eq!(pool.acquire().await?.id, 1);
+    assert_eq!(pool.status().in_use, 0);
+
+    let discarding = RedisPool::builder(factory.clone())
+        .reset_policy(ResetPolicy::Discard)
+        .build()?;
+    let mut con = discarding.acquire().await?;
+    let id = con.id;
+    send(&mut con, &["MULTI"]).await?;
+    drop(con);
+    assert_ne!(discarding.acquire().await?.id, id);
+
+    let keeping = RedisPool::builder(factory.clone())
+        .reset_policy(ResetPolicy::Keep)
+        .health_check(NoCheck)
+        .build()?;
+    let mut con = keeping.acquire().await?;
+    let id = con.id;
+    send(&mut con, &["MULTI"]).await?;
+    drop(con);
+    let con = keeping.acquire().await?;
+    assert_eq!(
+        (con.id, con.sent.last().map(String::as_str)),
+        (id, Some("MULTI"))
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_factory_reusability() -> anyhow::Result<()> {
+    let factory = MockConnectionFactory::default();
+    let pool = RedisPool::builder(factory.clone())
+        .pool_size(2)
+        .health_check(NoCheck)
+        .build()?;
+
+    let queued = pool.acquire().await?;
+    let held = pool.acquire().await?;
+    drop(queued);
+    factory.retire_existing();
+
+    // The queued connection is closed on checkout, the held one on return,
+    // even though the health check never runs a command.
+    assert_eq!(pool.acquire().await?.id, 2);
+    drop(held);
+    assert_eq!(pool.status().idle, 1);
+    assert_eq!(pool.acquire().await?.id, 2);
+    assert_eq!(pool.status().total_evicted_unhealthy, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+pub async fn test_cancelled_waiters() -> anyhow::Result<()> {
+    let pool = RedisPool::builder(MockConnectionFactory::default())
+        .pool_size(1)
+        .con_limit(Some(1))
+        .build()?;
+
+    let held = pool.acquire().await?;
+    let waits = (0..100).map(|_| {
+        tokio::time::timeout(
+            Duration::from_millis(10),
+            pool.acquire_with_priority(Priority::Low),
+        )
+    });
+    assert!(join_all(waits).await.iter().all(Result::is_err));
+    assert_eq!(pool.waiters(Priority::Low), 0);
+
+    // The waiters which gave up neither block new acquires nor take the
+    // released permit.
+    drop(held);
+    let con = pool.try_acquire().await?;
+    let waiting = tokio::spawn({
+        let pool = pool.clone();
+        async move { pool.acquire().await.map(|con| con.id) }
+    });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert_eq!(pool.waiters(Priority::Normal), 1);
+    drop(con);
+    assert_eq!(waiting.await??, 0);
+
+    Ok(())
+}
@@ -1,8 +1,12 @@
-/*mod utils;
+mod utils;
 
 use futures::StreamExt;
-use redis_pool::pool::RedisPool;
+use redis_pool::{
+    errors::RedisPoolError, monitor::MonitorEvent, pool::RedisPool, RedisPoolBuilder,
+};
+use std::time::{Duration, UNIX_EPOCH};
 use testcontainers::clients::{self};
+use tokio::sync::mpsc;
 use utils::TestRedis;
 
 #[tokio::test]
@@ -11,10 +15,10 @@ pub async fn test_pubsub() {
     let redis = TestRedis::new(&docker);
     let pool = RedisPool::from(redis.client());
 
-    let mut rx = pool.aquire().await.unwrap().into_pubsub();
-    let _ = rx.subscribe("test_channel").await;
+    let mut rx = pool.pubsub().await.unwrap();
+    rx.subscribe("test_channel").await.unwrap();
 
-    let mut tx = pool.aquire().await.unwrap();
+    let mut tx = pool.acquire().await.unwrap();
     let _: () = redis::cmd("PUBLISH")
         .arg("test_channel")
         .arg("test")
@@ -34,47 +38,183 @@ pub async fn test_pubsub() {
 }
 
 #[tokio::test]
-pub async fn test_monitor() {
+pub async fn test_pubsub_holds_permit() {
+    let docker = clients::Cli::default();
+    let redis = TestRedis::new(&docker);
+    let pool = RedisPoolBuilder::new(redis.client())
+        .pool_size(1)
+        .con_limit(Some(1))
+        .acquire_timeout(Duration::from_millis(50))
+        .build()
+        .unwrap();
+
+    let pubsub = pool.pubsub().await.unwrap();
+    assert_eq!(pool.status().in_use, 1);
+    assert!(matches!(pool.acquire().await, Err(RedisPoolError::Timeout)));
+
+    drop(pubsub);
+    assert_eq!(pool.status().in_use, 0);
+    assert!(pool.acquire().await.is_ok());
+}
+
+#[tokio::test]
+pub async fn test_subscription_hub() {
+    let docker = clients::Cli::default();
+    let redis = TestRedis::new(&docker);
+    let pool = RedisPool::from(redis.client());
+    let hub = pool.subscription_hub().await.unwrap();
+
+    let mut first = hub.subscribe("hub_channel").await.unwrap();
+    let mut second = hub.subscribe("hub_channel").await.unwrap();
+    let mut pattern = hub.psubscribe("hub_*").await.unwrap();
+    let (tx, mut forwarded) = mpsc::channel(8);
+    hub.subscribe("hub_channel").await.unwrap().forward(tx);
+
+    let mut con = pool.acquire().await.unwrap();
+    let receivers: usize = redis::cmd("PUBLISH")
+        .arg("hub_channel")
+        .arg("test")
+        .query_async(&mut con)
+        .await
+        .unwrap();
+    // One subscription to the channel and one to the pattern, shared by all.
+    assert_eq!(receivers, 2);
+
+    for sub in [&mut first, &mut second, &mut pattern] {
+        let msg = sub.recv().await.unwrap();
+        assert_eq!(msg.get_payload::<String>().unwrap(), "test");
+    }
+    let msg = forwarded.recv().await.unwrap();
+    assert_eq!(msg.get_channel_name(), "hub_channel");
+
+    // The hub reconnects and subscribes again once its connection is killed.
+    let _: () = redis::cmd("CLIENT")
+        .arg("KILL")
+        .arg("TYPE")
+        .arg("pubsub")
+        .query_async(&mut con)
+        .await
+        .unwrap();
+
+    let mut receivers = 0;
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        receivers = redis::cmd("PUBLISH")
+            .arg("hub_channel")
+            .arg("again")
+            .query_async(&mut con)
+            .await
+            .unwrap();
+        if receivers > 0 {
+            break;
+        }
+    }
+    assert!(receivers > 0);
+
+    loop {
+        let msg = first.recv().await.unwrap();
+        if msg.get_payload::<String>().unwrap() == "again" {
+            break;
+        }
+    }
+
+    // Dropping every subscription unsubscribes the channel.
+    drop((first, second, pattern, forwarded));
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let channels: Vec<String> = redis::cmd("PUBSUB")
+        .arg("CHANNELS")
+        .query_async(&mut con)
+        .await
+        .unwrap();
+    assert!(channels.is_empty());
+}
+
+#[tokio::test]
+pub async fn test_subscription_hub_cancelled_subscribe() {
     let docker = clients::Cli::default();
     let redis = TestRedis::new(&docker);
     let pool = RedisPool::from(redis.client());
+    let hub = pool.subscription_hub().await.unwrap();
+
+    // The subscribe command is queued on the first poll, then given up on.
+    let cancelled = tokio::time::timeout(Duration::ZERO, hub.subscribe("cancelled_channel")).await;
+    assert!(cancelled.is_err());
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let mut con = pool.acquire().await.unwrap();
+    let channels: Vec<String> = redis::cmd("PUBSUB")
+        .arg("CHANNELS")
+        .query_async(&mut con)
+        .await
+        .unwrap();
+    assert!(channels.is_empty());
 
-    let mut rx = pool
-        .factory()
-        .get_async_connection()
+    // A later subscription to the same channel subscribes it again.
+    let _sub = hub.subscribe("cancelled_channel").await.unwrap();
+    let channels: Vec<String> = redis::cmd("PUBSUB")
+        .arg("CHANNELS")
+        .query_async(&mut con)
         .await
-        .unwrap()
-        .into_monitor();
-    let _ = rx.monitor().await;
+        .unwrap();
+    assert_eq!(channels, ["cancelled_channel"]);
+}
+
+#[tokio::test]
+pub async fn test_monitor() {
+    let docker = clients::Cli::default();
+    let redis = TestRedis::new(&docker);
+    let pool = RedisPoolBuilder::new(redis.client())
+        .con_limit(Some(2))
+        .build()
+        .unwrap();
 
-    let mut tx = pool.aquire().await.unwrap();
+    let mut rx = pool.monitor().await.unwrap();
+    assert_eq!(pool.status().in_use, 1);
+
+    let mut tx = pool.acquire().await.unwrap();
     let _: () = redis::cmd("PING")
         .arg("test")
         .query_async(&mut tx)
         .await
         .unwrap();
 
-    let mut i = 0;
     let mut was_ping = false;
 
-    while let Some(string) = rx.on_message::<String>().next().await {
-        let monitor = string.split(' ').collect::<Vec<_>>();
-        if "\"PING\"" == monitor[3] {
+    for _ in 0..4 {
+        let event = rx.next().await.unwrap();
+        if event.command.eq_ignore_ascii_case("PING") {
+            assert_eq!(event.db, 0);
+            assert_eq!(event.args, vec![b"test".to_vec()]);
             was_ping = true;
             break;
         }
+    }
 
-        let _: () = redis::cmd("PING")
-            .arg("test")
-            .query_async(&mut tx)
-            .await
+    assert!(was_ping);
+    drop(rx);
+    assert_eq!(pool.status().in_use, 1);
+}
+
+#[test]
+pub fn test_monitor_event_parse() {
+    let event =
+        MonitorEvent::parse(r#"1339518083.107412 [0 127.0.0.1:60866] "set" "k\"1" "a\x00b\n""#)
             .unwrap();
-        i += 1;
 
-        if i > 3 {
-            break;
-        }
-    }
+    assert_eq!(
+        event.timestamp,
+        UNIX_EPOCH + Duration::from_secs_f64(1339518083.107412)
+    );
+    assert_eq!(event.db, 0);
+    assert_eq!(event.client, "127.0.0.1:60866");
+    assert_eq!(event.command, "set");
+    assert_eq!(event.args, vec![b"k\"1".to_vec(), b"a\x00b\n".to_vec()]);
 
-    assert!(was_ping);
-}*/
+    let event = MonitorEvent::parse(r#"1339518083.107412 [3 lua] "ping""#).unwrap();
+    assert_eq!(event.db, 3);
+    assert_eq!(event.client, "lua");
+    assert!(event.args.is_empty());
+
+    assert!(MonitorEvent::parse("OK").is_none());
+    assert!(MonitorEvent::parse(r#"1339518083.107412 [0 lua] "unterminated"#).is_none());
+}
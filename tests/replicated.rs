@@ -0,0 +1,171 @@
+mod utils;
+
+use redis_pool::{
+    health::NoCheck,
+    pool::RedisPool,
+    replicated::{LoadBalancing, ReplicatedRedisPool},
+};
+use std::time::Duration;
+use tokio::time::timeout;
+use utils::{MockConnection, MockConnectionFactory};
+
+fn mock_pool(factory: &MockConnectionFactory) -> RedisPool<MockConnectionFactory, MockConnection> {
+    RedisPool::builder(factory.clone())
+        .pool_size(4)
+        .con_limit(Some(4))
+        .build()
+        .unwrap()
+}
+
+fn replicated(
+    load_balancing: LoadBalancing,
+) -> (
+    ReplicatedRedisPool<MockConnectionFactory, MockConnection>,
+    Vec<MockConnectionFactory>,
+) {
+    let factories: Vec<_> = (0..3).map(|_| MockConnectionFactory::default()).collect();
+    let pool = ReplicatedRedisPool::new(
+        mock_pool(&factories[0]),
+        factories[1..].iter().map(mock_pool).collect(),
+    )
+    .load_balancing(load_balancing);
+
+    (pool, factories)
+}
+
+fn in_use(pool: &ReplicatedRedisPool<MockConnectionFactory, MockConnection>) -> Vec<usize> {
+    std::iter::once(pool.primary())
+        .chain(pool.replicas())
+        .map(|pool| pool.status().in_use)
+        .collect()
+}
+
+#[tokio::test]
+pub async fn test_write_uses_primary() {
+    let (pool, _) = replicated(LoadBalancing::RoundRobin);
+
+    let _con = pool.acquire_write().await.unwrap();
+    assert_eq!(in_use(&pool), vec![1, 0, 0]);
+}
+
+#[tokio::test]
+pub async fn test_round_robin() {
+    let (pool, _) = replicated(LoadBalancing::RoundRobin);
+
+    let first = pool.acquire_read().await.unwrap();
+    let second = pool.acquire_read().await.unwrap();
+    assert_eq!(in_use(&pool), vec![0, 1, 1]);
+
+    drop((first, second));
+    let _third = pool.acquire_read().await.unwrap();
+    assert_eq!(in_use(&pool), vec![0, 1, 0]);
+}
+
+#[tokio::test]
+pub async fn test_least_in_use() {
+    let (pool, _) = replicated(LoadBalancing::LeastInUse);
+
+    let _busy = pool.replicas()[0].acquire().await.unwrap();
+    let _busy2 = pool.replicas()[0].acquire().await.unwrap();
+    let _read = pool.acquire_read().await.unwrap();
+    let _read2 = pool.acquire_read().await.unwrap();
+    assert_eq!(in_use(&pool), vec![0, 2, 2]);
+}
+
+#[tokio::test]
+pub async fn test_random() {
+    let (pool, _) = replicated(LoadBalancing::Random);
+
+    let _reads = [
+        pool.acquire_read().await.unwrap(),
+        pool.acquire_read().await.unwrap(),
+    ];
+    let in_use = in_use(&pool);
+    assert_eq!(in_use[0], 0);
+    assert_eq!(in_use[1] + in_use[2], 2);
+}
+
+#[tokio::test]
+pub async fn test_fallback_to_primary() {
+    let (pool, factories) = replicated(LoadBalancing::RoundRobin);
+
+    // Queue a connection in each replica, then make them fail the health check
+    // and refuse new connections.
+    for replica in pool.replicas() {
+        drop(replica.acquire().await.unwrap());
+    }
+    for factory in &factories[1..] {
+        factory.set_failing(true);
+    }
+
+    let con = pool.acquire_read().await.unwrap();
+    assert_eq!(in_use(&pool), vec![1, 0, 0]);
+    assert!(pool
+        .replicas()
+        .iter()
+        .all(|replica| replica.status().total_evicted_unhealthy == 1));
+    drop(con);
+
+    // A replica is used again once it recovers.
+    factories[2].set_failing(false);
+    let _con = pool.acquire_read().await.unwrap();
+    assert_eq!(in_use(&pool), vec![0, 0, 1]);
+}
+
+#[tokio::test]
+pub async fn test_without_replicas() {
+    let factory = MockConnectionFactory::default();
+    let primary = RedisPool::builder(factory.clone())
+        .health_check(NoCheck)
+        .build()
+        .unwrap();
+    let pool = ReplicatedRedisPool::new(primary, Vec::new());
+
+    let _con = pool.acquire_read().await.unwrap();
+    assert_eq!(pool.primary().status().in_use, 1);
+}
+
+#[tokio::test]
+pub async fn test_saturated_replica() {
+    let (pool, _factories) = replicated(LoadBalancing::RoundRobin);
+
+    // The first replica is at its limit, so the round-robin read routed to it
+    // moves on to the next replica instead of waiting.
+    let mut held: Vec<_> = Vec::new();
+    for _ in 0..4 {
+        held.push(pool.replicas()[0].acquire().await.unwrap());
+    }
+    let read = timeout(Duration::from_secs(1), pool.acquire_read())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(in_use(&pool), vec![0, 4, 1]);
+    drop(read);
+
+    // The primary serves reads once every replica is saturated.
+    for _ in 0..4 {
+        held.push(pool.replicas()[1].acquire().await.unwrap());
+    }
+    let read = pool.acquire_read().await.unwrap();
+    assert_eq!(in_use(&pool), vec![1, 4, 4]);
+
+    // With the primary saturated as well, the read waits for the first pool
+    // to free a connection.
+    for _ in 0..3 {
+        held.push(pool.acquire_write().await.unwrap());
+    }
+    let waiting = tokio::spawn({
+        let pool = pool.clone();
+        async move { pool.acquire_read().await.map(|_| ()) }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(!waiting.is_finished());
+
+    drop(held.remove(0));
+    timeout(Duration::from_secs(1), waiting)
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    drop(read);
+}
@@ -0,0 +1,60 @@
+//! Run with `--features runtime-async-std` or
+//! `--no-default-features --features runtime-smol`.
+#![cfg(any(feature = "runtime-async-std", feature = "runtime-smol"))]
+
+mod utils;
+
+use redis_pool::{errors::RedisPoolError, pool::RedisPool};
+use std::time::Duration;
+use utils::MockConnectionFactory;
+
+/// Limits, timeouts, background refills and closing of a pool, run outside
+/// of a tokio runtime.
+async fn exercise_pool() -> anyhow::Result<()> {
+    let factory = MockConnectionFactory::default();
+    let pool = RedisPool::builder(factory.clone())
+        .pool_size(2)
+        .con_limit(Some(2))
+        .min_idle(2)
+        .idle_timeout(Duration::from_millis(30))
+        .build()?;
+
+    assert_eq!(pool.warm_up().await?, 2);
+
+    let first = pool.acquire().await?;
+    let second = pool.try_acquire().await?;
+
+    assert!(matches!(
+        pool.acquire_with_timeout(Duration::from_millis(20)).await,
+        Err(RedisPoolError::Timeout)
+    ));
+
+    // The maintenance task closes the idle connections and creates new ones.
+    drop((first, second));
+    smol::Timer::after(Duration::from_millis(150)).await;
+    assert!(factory.created() > 2);
+
+    let held = pool.acquire().await?;
+    assert!(matches!(
+        pool.close(Duration::from_millis(20)).await,
+        Err(RedisPoolError::Timeout)
+    ));
+
+    drop(held);
+    assert!(matches!(pool.acquire().await, Err(RedisPoolError::Closed)));
+
+    Ok(())
+}
+
+#[cfg(feature = "runtime-async-std")]
+#[async_std::test]
+pub async fn test_async_std_runtime() -> anyhow::Result<()> {
+    exercise_pool().await
+}
+
+// async-std takes precedence outside of tokio when both are enabled.
+#[cfg(all(feature = "runtime-smol", not(feature = "runtime-async-std")))]
+#[test]
+pub fn test_smol_runtime() -> anyhow::Result<()> {
+    smol::block_on(exercise_pool())
+}
@@ -0,0 +1,77 @@
+mod utils;
+
+use redis_pool::SentinelRedisPool;
+use serial_test::serial;
+use std::time::Duration;
+use testcontainers::clients::Cli;
+use utils::TestSentinelRedis;
+
+#[tokio::test]
+#[serial]
+pub async fn test_simple_get_set() -> anyhow::Result<()> {
+    let docker = Cli::docker();
+    let sentinel = TestSentinelRedis::new(&docker);
+    let pool = SentinelRedisPool::from(sentinel.factory());
+
+    for i in 0..50 {
+        let mut con = pool.acquire().await?;
+        let (value,) = redis::Pipeline::with_capacity(2)
+            .set("test", i)
+            .ignore()
+            .get("test")
+            .query_async::<(i64,)>(&mut con)
+            .await?;
+        assert_eq!(i, value);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+pub async fn test_failover() -> anyhow::Result<()> {
+    let docker = Cli::docker();
+    let sentinel = TestSentinelRedis::new(&docker);
+    let pool = SentinelRedisPool::from(sentinel.factory());
+
+    let mut con = pool.acquire().await?;
+    let _: () = redis::cmd("SET")
+        .arg("test")
+        .arg(1)
+        .query_async(&mut con)
+        .await?;
+    drop(con);
+
+    let mut sentinel_con = sentinel.client().get_multiplexed_async_connection().await?;
+    let _: () = redis::cmd("SENTINEL")
+        .arg("FAILOVER")
+        .arg(sentinel.service_name())
+        .query_async(&mut sentinel_con)
+        .await?;
+
+    // Writes fail while the replica is promoted, then reach the new master.
+    let mut written = false;
+
+    for i in 0..100 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let Ok(mut con) = pool.acquire().await else {
+            continue;
+        };
+
+        if redis::cmd("SET")
+            .arg("test")
+            .arg(i)
+            .query_async::<()>(&mut con)
+            .await
+            .is_ok()
+            && pool.status().total_evicted_unhealthy > 0
+        {
+            written = true;
+            break;
+        }
+    }
+
+    assert!(written);
+    Ok(())
+}
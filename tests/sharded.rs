@@ -0,0 +1,125 @@
+mod utils;
+
+use redis_pool::{errors::ConfigError, pool::RedisPool, sharded::ShardedRedisPool};
+use std::collections::HashMap;
+use utils::{MockConnection, MockConnectionFactory};
+
+type MockShardedPool = ShardedRedisPool<MockConnectionFactory, MockConnection>;
+
+fn mock_pool() -> RedisPool<MockConnectionFactory, MockConnection> {
+    RedisPool::builder(MockConnectionFactory::default())
+        .build()
+        .unwrap()
+}
+
+fn sharded(weights: &[(&str, usize)]) -> MockShardedPool {
+    weights
+        .iter()
+        .fold(ShardedRedisPool::builder(), |builder, &(name, weight)| {
+            builder.weighted_shard(name, mock_pool(), weight)
+        })
+        .build()
+        .unwrap()
+}
+
+fn owners(pool: &MockShardedPool) -> Vec<String> {
+    (0..10_000)
+        .map(|i| pool.shard_for_key(format!("key:{}", i)).name().to_owned())
+        .collect()
+}
+
+#[test]
+pub fn test_builder_validation() {
+    let err = |builder: redis_pool::sharded::ShardedRedisPoolBuilder<_, _>| builder.build().err();
+
+    assert_eq!(
+        err(MockShardedPool::builder()),
+        Some(ConfigError::NoEndpoints)
+    );
+    assert_eq!(
+        err(MockShardedPool::builder().weighted_shard("a", mock_pool(), 0)),
+        Some(ConfigError::ZeroShardWeight)
+    );
+    assert_eq!(
+        err(MockShardedPool::builder()
+            .shard("a", mock_pool())
+            .virtual_nodes(0)),
+        Some(ConfigError::ZeroVirtualNodes)
+    );
+    assert_eq!(
+        err(MockShardedPool::builder()
+            .shard("a", mock_pool())
+            .shard("a", mock_pool())),
+        Some(ConfigError::DuplicateShard("a".to_owned()))
+    );
+}
+
+#[test]
+pub fn test_hash_tags() {
+    let pool = sharded(&[("a", 1), ("b", 1), ("c", 1)]);
+
+    for user in 0..100 {
+        let owner = pool.shard_for_key(format!("{{user:{}}}:name", user)).name();
+        assert_eq!(
+            owner,
+            pool.shard_for_key(format!("{{user:{}}}:email", user))
+                .name()
+        );
+        assert_eq!(owner, pool.shard_for_key(format!("user:{}", user)).name());
+    }
+
+    // Empty tags hash the whole key, like in Redis Cluster.
+    let spread: std::collections::HashSet<_> = (0..100)
+        .map(|i| pool.shard_for_key(format!("{{}}:{}", i)).name())
+        .collect();
+    assert!(spread.len() > 1);
+}
+
+#[test]
+pub fn test_weights() {
+    let pool = sharded(&[("a", 1), ("b", 1), ("c", 2)]);
+
+    let mut counts = HashMap::new();
+    for owner in owners(&pool) {
+        *counts.entry(owner).or_insert(0) += 1;
+    }
+
+    // Expect 2500, 2500 and 5000 keys, give or take the ring imbalance.
+    assert!((2000..3000).contains(&counts["a"]));
+    assert!((2000..3000).contains(&counts["b"]));
+    assert!((4300..5700).contains(&counts["c"]));
+}
+
+#[test]
+pub fn test_consistency() {
+    let before = owners(&sharded(&[("a", 1), ("b", 1), ("c", 1)]));
+    let after = owners(&sharded(&[("a", 1), ("b", 1), ("c", 1), ("d", 1)]));
+
+    // Only keys moved to the new shard change owner.
+    let moved: Vec<_> = before
+        .iter()
+        .zip(&after)
+        .filter(|(before, after)| before != after)
+        .collect();
+    assert!(moved.iter().all(|(_, after)| after.as_str() == "d"));
+    assert!((1500..3500).contains(&moved.len()));
+
+    // Placement only depends on the shard names.
+    assert_eq!(before, owners(&sharded(&[("c", 1), ("a", 1), ("b", 1)])));
+}
+
+#[tokio::test]
+pub async fn test_acquire_for_key() {
+    let pool = sharded(&[("a", 1), ("b", 1)]);
+
+    let _con = pool.acquire_for_key("{user:1}:name").await.unwrap();
+    let owner = pool.shard_for_key("user:1");
+    assert_eq!(owner.pool().status().in_use, 1);
+    assert_eq!(
+        pool.shards()
+            .iter()
+            .map(|shard| shard.pool().status().in_use)
+            .sum::<usize>(),
+        1
+    );
+}
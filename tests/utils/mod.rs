@@ -1,12 +1,19 @@
 #![allow(dead_code)]
 
-use std::ops::Deref;
+use std::{
+    ops::Deref,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use async_trait::async_trait;
 use futures::FutureExt;
 use redis::{
     aio::{ConnectionLike, MultiplexedConnection},
-    Client, Cmd, ErrorKind, RedisError, RedisFuture, RedisResult, Value,
+    Arg, Client, Cmd, ErrorKind, RedisError, RedisFuture, RedisResult, Value,
 };
 use redis_pool::factory::ConnectionFactory;
 use testcontainers::{
@@ -23,6 +30,12 @@ const REDIS_CLUSTER_IMG_VER: &str = "latest";
 const REDIS_CLUSTER_PORTS: [u16; 3] = [6379, 6380, 6380];
 const REDIS_CLUSTER_READY_MSG: &str = "CLUSTER READY!";
 
+const REDIS_SENTINEL_IMG_NAME: &str = "redis-sentinel";
+const REDIS_SENTINEL_IMG_VER: &str = "latest";
+const REDIS_SENTINEL_PORT: u16 = 26379;
+const REDIS_SENTINEL_SERVICE: &str = "mymaster";
+const REDIS_SENTINEL_READY_MSG: &str = "SENTINEL READY!";
+
 fn create_redis_image() -> GenericImage {
     let wait = WaitFor::message_on_stdout(REDIS_READY_MSG);
     GenericImage::new(REDIS_IMG_NAME, REDIS_IMG_VER)
@@ -37,6 +50,13 @@ fn create_redis_cluster_image() -> RunnableImage<GenericImage> {
     RunnableImage::from(image).with_network("host")
 }
 
+fn create_redis_sentinel_image() -> RunnableImage<GenericImage> {
+    let wait = WaitFor::message_on_stdout(REDIS_SENTINEL_READY_MSG);
+    let image =
+        GenericImage::new(REDIS_SENTINEL_IMG_NAME, REDIS_SENTINEL_IMG_VER).with_wait_for(wait);
+    RunnableImage::from(image).with_network("host")
+}
+
 pub struct TestRedis<'a> {
     container: Container<'a, GenericImage>,
 }
@@ -93,6 +113,35 @@ impl<'a> TestClusterRedis<'a> {
     }
 }
 
+pub struct TestSentinelRedis<'a> {
+    container: Container<'a, GenericImage>,
+}
+
+impl<'a> TestSentinelRedis<'a> {
+    pub fn new(docker: &'a Cli) -> Self {
+        TestSentinelRedis {
+            container: docker.run(create_redis_sentinel_image()),
+        }
+    }
+
+    pub fn service_name(&self) -> &'static str {
+        REDIS_SENTINEL_SERVICE
+    }
+
+    pub fn client(&self) -> redis::Client {
+        redis::Client::open(format!("redis://127.0.0.1:{}/", REDIS_SENTINEL_PORT))
+            .expect("Client failed to connect")
+    }
+
+    pub fn factory(&self) -> redis_pool::sentinel::SentinelFactory {
+        redis_pool::sentinel::SentinelFactory::new(
+            vec![format!("redis://127.0.0.1:{}/", REDIS_SENTINEL_PORT)],
+            REDIS_SENTINEL_SERVICE,
+        )
+        .expect("Factory failed to build")
+    }
+}
+
 #[derive(Clone)]
 pub struct ClosableConnectionFactory(pub Client);
 
@@ -150,3 +199,144 @@ impl ConnectionLike for ClosableConnection {
         }
     }
 }
+
+/// Factory for connections which answer locally, so pool behaviour can be
+/// tested without a running redis server.
+#[derive(Clone, Default)]
+pub struct MockConnectionFactory {
+    pub created: Arc<AtomicUsize>,
+    pub delay: Option<Duration>,
+    pub failing: Arc<AtomicBool>,
+    pub attempts: Arc<AtomicUsize>,
+    pub fail_next: Arc<AtomicUsize>,
+    /// Connections with a lower id are no longer reusable.
+    pub reusable_from: Arc<AtomicUsize>,
+}
+
+impl MockConnectionFactory {
+    pub fn with_delay(delay: Duration) -> Self {
+        MockConnectionFactory {
+            delay: Some(delay),
+            ..Default::default()
+        }
+    }
+
+    pub fn created(&self) -> usize {
+        self.created.load(Ordering::SeqCst)
+    }
+
+    /// Number of times `create` was called, failed calls included.
+    pub fn attempts(&self) -> usize {
+        self.attempts.load(Ordering::SeqCst)
+    }
+
+    /// Fails the next `count` calls to `create`.
+    pub fn fail_next(&self, count: usize) {
+        self.fail_next.store(count, Ordering::SeqCst);
+    }
+
+    /// Makes every connection created so far no longer reusable, like a
+    /// failover does to the connections of a sentinel factory.
+    pub fn retire_existing(&self) {
+        self.reusable_from.store(self.created(), Ordering::SeqCst);
+    }
+
+    /// Makes new connections fail to be created and the existing ones fail
+    /// every command.
+    pub fn set_failing(&self, failing: bool) {
+        self.failing.store(failing, Ordering::SeqCst);
+    }
+}
+
+#[async_trait]
+impl ConnectionFactory<MockConnection> for MockConnectionFactory {
+    async fn create(&self) -> RedisResult<MockConnection> {
+        if let Some(delay) = self.delay {
+            tokio::time::sleep(delay).await;
+        }
+
+        self.attempts.fetch_add(1, Ordering::SeqCst);
+
+        let fail_next = self
+            .fail_next
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            .is_ok();
+
+        if fail_next || self.failing.load(Ordering::SeqCst) {
+            return Err(RedisError::from((ErrorKind::Io, "connection refused")));
+        }
+
+        let id = self.created.fetch_add(1, Ordering::SeqCst);
+        Ok(MockConnection {
+            id,
+            open: true,
+            failing: self.failing.clone(),
+            sent: Vec::new(),
+        })
+    }
+
+    fn is_reusable(&self, con: &MockConnection) -> bool {
+        con.id >= self.reusable_from.load(Ordering::SeqCst)
+    }
+}
+
+pub struct MockConnection {
+    pub id: usize,
+    pub open: bool,
+    pub failing: Arc<AtomicBool>,
+    /// Commands received, with their arguments separated by spaces.
+    pub sent: Vec<String>,
+}
+
+impl MockConnection {
+    fn reply(&mut self, cmd: &Cmd) -> RedisResult<Value> {
+        if !self.open || self.failing.load(Ordering::SeqCst) {
+            return Err(RedisError::from((ErrorKind::Io, "closed connection")));
+        }
+
+        let mut args = cmd.args_iter().filter_map(|arg| match arg {
+            Arg::Simple(arg) => Some(arg.to_vec()),
+            _ => None,
+        });
+        self.sent.push(
+            args.clone()
+                .map(|arg| String::from_utf8_lossy(&arg).into_owned())
+                .collect::<Vec<_>>()
+                .join(" "),
+        );
+
+        match args.next().as_deref() {
+            Some(b"PING") => Ok(args
+                .next()
+                .map(Value::BulkString)
+                .unwrap_or_else(|| Value::SimpleString("PONG".into()))),
+            _ => Ok(Value::Okay),
+        }
+    }
+}
+
+impl ConnectionLike for MockConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        let res = self.reply(cmd);
+        (async move { res }).boxed()
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        cmd: &'a redis::Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> redis::RedisFuture<'a, Vec<redis::Value>> {
+        let res = cmd
+            .cmd_iter()
+            .skip(offset)
+            .take(count)
+            .map(|cmd| self.reply(cmd))
+            .collect();
+        (async move { res }).boxed()
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+}